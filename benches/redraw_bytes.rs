@@ -0,0 +1,201 @@
+//! Bytes sent to the terminal for common interactions, drawn straight to the
+//! terminal as before and through the diffing renderer.
+//!
+//! ```bash
+//! cargo bench --bench redraw_bytes
+//! ```
+
+use std::io::{self, sink};
+use std::time::Instant;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::queue;
+use crossterm::terminal::{Clear, ClearType};
+use tui_color_picker::color_space::ColorSettings;
+use tui_color_picker::constants::*;
+use tui_color_picker::crossterm_commands::ResetDefaultColors;
+use tui_color_picker::render::{FrameStats, Renderer};
+use tui_color_picker::types::Vec2;
+use tui_color_picker::ui::hue_picker::HuePicker;
+use tui_color_picker::ui::inputs::{Focus, Inputs};
+use tui_color_picker::ui::saturation_value_picker::SaturationValuePicker;
+use tui_color_picker::ui::value_display::draw_value_display;
+
+const OFFSET: Vec2 = Vec2 { x: 1, y: 10 };
+/// Drag events a terminal typically sends between two reads
+const BURST: usize = 8;
+
+struct Picker {
+    sv_picker: SaturationValuePicker,
+    hue_picker: HuePicker,
+    inputs: Inputs,
+    settings: ColorSettings,
+    renderer: Renderer,
+}
+
+impl Picker {
+    fn new() -> io::Result<Picker> {
+        let mut renderer = Renderer::new(120, 50);
+        // As the picker starts
+        queue!(renderer, ResetDefaultColors(false), Clear(ClearType::All))?;
+        Ok(Picker {
+            sv_picker: SaturationValuePicker::new(
+                SV_PICKER_REL_POS + OFFSET,
+                SV_PICKER_WIDTH,
+                SV_PICKER_HEIGHT,
+            ),
+            hue_picker: HuePicker::new(
+                HUE_PICKER_REL_POS + OFFSET,
+                HUE_PICKER_WIDTH,
+                HUE_PICKER_HEIGHT,
+            ),
+            inputs: Inputs::new(INPUTS_REL_POS + OFFSET),
+            settings: ColorSettings::default(),
+            renderer,
+        })
+    }
+
+    fn draw_values(&mut self) -> io::Result<()> {
+        let color = self.sv_picker.selected_color;
+        draw_value_display(
+            &mut self.renderer,
+            &(VALUE_DISPLAY_REL_POS + OFFSET),
+            &color,
+            &self.settings,
+            false,
+        )?;
+        self.inputs
+            .draw(&mut self.renderer, &color, &self.settings, false)
+    }
+
+    fn draw_all(&mut self) -> io::Result<()> {
+        self.draw_values()?;
+        self.sv_picker
+            .draw(&mut self.renderer, &self.settings, false)?;
+        self.hue_picker
+            .draw(&mut self.renderer, &self.settings, false)
+    }
+
+    /// What a drag on the hue strip redraws
+    fn drag_hue(&mut self, y: u32) -> io::Result<()> {
+        if let Ok(hue) = self.hue_picker.get(0, y) {
+            self.sv_picker.set_hue(hue);
+        }
+        self.draw_values()?;
+        self.sv_picker
+            .draw(&mut self.renderer, &self.settings, false)
+    }
+
+    /// What a drag on the saturation and value square redraws
+    fn drag_sv(&mut self, x: u32, y: u32) -> io::Result<()> {
+        let _ = self.sv_picker.change_color(x, y);
+        self.draw_values()
+    }
+
+    fn type_char(&mut self, c: char) {
+        let event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        self.inputs.value_input(&mut self.renderer, event);
+    }
+
+    fn present(&mut self) -> io::Result<FrameStats> {
+        self.renderer.present(&mut sink())
+    }
+}
+
+struct Row {
+    name: &'static str,
+    events: usize,
+    before: usize,
+    after: usize,
+    micros: u128,
+}
+
+/// Runs `interaction` `events` times, drawing each event straight to the
+/// terminal before and presenting one frame per `burst` events now
+fn measure(
+    picker: &mut Picker,
+    name: &'static str,
+    events: usize,
+    burst: usize,
+    mut interaction: impl FnMut(&mut Picker, usize) -> io::Result<()>,
+) -> io::Result<Row> {
+    let start = Instant::now();
+    let (mut before, mut after) = (0, 0);
+    for i in 0..events {
+        interaction(picker, i)?;
+        if (i + 1) % burst == 0 || i + 1 == events {
+            let stats = picker.present()?;
+            before += stats.drawn;
+            after += stats.emitted;
+        }
+    }
+    Ok(Row {
+        name,
+        events,
+        before,
+        after,
+        micros: start.elapsed().as_micros(),
+    })
+}
+
+fn main() -> io::Result<()> {
+    let mut picker = Picker::new()?;
+    let mut rows = vec![measure(&mut picker, "first frame", 1, 1, |picker, _| {
+        picker.draw_all()
+    })?];
+    rows.push(measure(
+        &mut picker,
+        "redraw unchanged",
+        1,
+        1,
+        |picker, _| picker.draw_all(),
+    )?);
+    let hue_rows = HUE_PICKER_HEIGHT * 2;
+    rows.push(measure(&mut picker, "hue drag", 32, 1, |picker, i| {
+        picker.drag_hue(i as u32 % hue_rows)
+    })?);
+    rows.push(measure(
+        &mut picker,
+        "hue drag, bursts",
+        32,
+        BURST,
+        |picker, i| picker.drag_hue(i as u32 % hue_rows),
+    )?);
+    rows.push(measure(&mut picker, "value drag", 32, 1, |picker, i| {
+        picker.drag_sv(20, i as u32)
+    })?);
+    rows.push(measure(
+        &mut picker,
+        "value drag, bursts",
+        32,
+        BURST,
+        |picker, i| picker.drag_sv(20, i as u32),
+    )?);
+    picker.inputs.focus = Focus::Hex;
+    picker.inputs.gain_focus(
+        &mut picker.renderer,
+        &picker.sv_picker.selected_color,
+        &picker.settings,
+    )?;
+    picker.present()?;
+    rows.push(measure(&mut picker, "typing a hex", 6, 1, |picker, i| {
+        picker.type_char(['f', 'f', '5', '7', '3', '3'][i]);
+        Ok(())
+    })?);
+
+    println!(
+        "{:<20} {:>6} {:>12} {:>12} {:>7} {:>10}",
+        "interaction", "events", "before (B)", "after (B)", "ratio", "time (us)"
+    );
+    for row in rows {
+        let ratio = match row.before {
+            0 => 0.0,
+            before => row.after as f64 / before as f64 * 100.0,
+        };
+        println!(
+            "{:<20} {:>6} {:>12} {:>12} {:>6.1}% {:>10}",
+            row.name, row.events, row.before, row.after, ratio, row.micros
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+//! Minimal client for the `--listen` socket of the picker.
+//!
+//! Sends each argument, or each line of the standard input when there are
+//! none, as a request and prints the replies. After a `subscribe` request it
+//! keeps printing the change events until the picker exits.
+//!
+//! ```bash
+//! tui-color-picker --listen /tmp/picker.sock
+//! cargo run --example ipc_client -- /tmp/picker.sock \
+//!     '{"cmd": "set", "color": "#ff5733"}' '{"cmd": "subscribe"}'
+//! ```
+
+#[cfg(unix)]
+fn main() -> std::io::Result<()> {
+    use std::io::{BufRead, BufReader, Write, stdin};
+    use std::os::unix::net::UnixStream;
+
+    let mut args = std::env::args().skip(1);
+    let Some(path) = args.next() else {
+        eprintln!("usage: ipc_client <SOCKET> [REQUEST]...");
+        std::process::exit(2);
+    };
+    let mut requests: Vec<String> = args.collect();
+    if requests.is_empty() {
+        requests = stdin().lock().lines().collect::<Result<_, _>>()?;
+    }
+
+    let mut stream = UnixStream::connect(path)?;
+    for request in &requests {
+        stream.write_all(request.trim().as_bytes())?;
+        stream.write_all(b"\n")?;
+    }
+    let subscribed = requests.iter().any(|request| request.contains("subscribe"));
+    let mut pending = requests.len();
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        println!("{}", line);
+        if !line.contains("\"event\"") {
+            pending = pending.saturating_sub(1);
+        }
+        if pending == 0 && !subscribed {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("ipc_client needs Unix domain sockets");
+}
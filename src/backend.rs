@@ -0,0 +1,83 @@
+use std::io::{self, Write, stdout};
+
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+
+// `State` draws into its `Renderer` and sends the frames, along with the
+// sequences that are not drawn (palette slots, mouse modes), to a backend. The
+// terminal backend owns the real terminal, the headless one lets the whole UI
+// run in tests.
+
+/// Where the frames and terminal sequences of `State` go
+pub trait Backend: Write {
+    /// Takes over the terminal: raw mode, alternate screen, mouse and paste
+    fn enter(&mut self) -> io::Result<()>;
+    /// Gives the terminal back as it was before `enter`
+    fn leave(&mut self) -> io::Result<()>;
+}
+
+/// The terminal the picker runs in, through stdout
+pub struct TerminalBackend;
+
+impl Write for TerminalBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        stdout().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        stdout().flush()
+    }
+}
+
+impl Backend for TerminalBackend {
+    fn enter(&mut self) -> io::Result<()> {
+        execute!(
+            stdout(),
+            Hide,
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableBracketedPaste,
+        )?;
+        enable_raw_mode()
+    }
+
+    fn leave(&mut self) -> io::Result<()> {
+        execute!(
+            stdout(),
+            DisableBracketedPaste,
+            DisableMouseCapture,
+            LeaveAlternateScreen,
+            Show
+        )?;
+        disable_raw_mode()
+    }
+}
+
+/// No terminal: the output is discarded, what the UI shows is read from the
+/// renderer of the state
+#[derive(Debug, Default)]
+pub struct HeadlessBackend;
+
+impl Write for HeadlessBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn enter(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn leave(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use palette::chromatic_adaptation::AdaptInto;
+use palette::convert::FromColorUnclamped;
+use palette::white_point::{D50, D65};
+use palette::{Lab, Lch, Xyz};
+
+/// Reference white of the Lab, LCh and XYZ values. The RGB spaces are D65,
+/// the values are chromatically adapted with the Bradford method for D50.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WhitePoint {
+    /// Used by CSS `lab()` and `lch()` and by most spectrophotometers
+    #[default]
+    D50,
+    D65,
+}
+
+impl WhitePoint {
+    pub const NAMES: &str = "d50, d65";
+
+    pub fn parse(name: &str) -> Option<WhitePoint> {
+        match name.to_ascii_lowercase().as_str() {
+            "d50" => Some(WhitePoint::D50),
+            "d65" => Some(WhitePoint::D65),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WhitePoint::D50 => "D50",
+            WhitePoint::D65 => "D65",
+        }
+    }
+
+    pub fn toggled(&self) -> WhitePoint {
+        match self {
+            WhitePoint::D50 => WhitePoint::D65,
+            WhitePoint::D65 => WhitePoint::D50,
+        }
+    }
+}
+
+/// XYZ with Y from 0 to 1
+pub fn xyz_components(xyz: Xyz<D65>, white_point: WhitePoint) -> (f32, f32, f32) {
+    match white_point {
+        WhitePoint::D50 => {
+            let adapted: Xyz<D50> = xyz.adapt_into();
+            adapted.into_components()
+        }
+        WhitePoint::D65 => xyz.into_components(),
+    }
+}
+
+pub fn xyz_from_components(x: f32, y: f32, z: f32, white_point: WhitePoint) -> Xyz<D65> {
+    match white_point {
+        WhitePoint::D50 => Xyz::<D50>::new(x, y, z).adapt_into(),
+        WhitePoint::D65 => Xyz::new(x, y, z),
+    }
+}
+
+/// CIELAB with L from 0 to 100
+pub fn lab_from_xyz(xyz: Xyz<D65>, white_point: WhitePoint) -> (f32, f32, f32) {
+    match white_point {
+        WhitePoint::D50 => {
+            let adapted: Xyz<D50> = xyz.adapt_into();
+            Lab::from_color_unclamped(adapted).into_components()
+        }
+        WhitePoint::D65 => Lab::<D65>::from_color_unclamped(xyz).into_components(),
+    }
+}
+
+pub fn xyz_from_lab(l: f32, a: f32, b: f32, white_point: WhitePoint) -> Xyz<D65> {
+    match white_point {
+        WhitePoint::D50 => Xyz::from_color_unclamped(Lab::<D50>::new(l, a, b)).adapt_into(),
+        WhitePoint::D65 => Xyz::from_color_unclamped(Lab::<D65>::new(l, a, b)),
+    }
+}
+
+/// Cylindrical CIELAB, the hue is in degrees from 0 to 360
+pub fn lch_from_xyz(xyz: Xyz<D65>, white_point: WhitePoint) -> (f32, f32, f32) {
+    let (l, a, b) = lab_from_xyz(xyz, white_point);
+    // The polar conversion does not depend on the white point
+    let lch = Lch::from_color_unclamped(Lab::<D50>::new(l, a, b));
+    (lch.l, lch.chroma, lch.hue.into_positive_degrees())
+}
+
+pub fn xyz_from_lch(l: f32, chroma: f32, hue: f32, white_point: WhitePoint) -> Xyz<D65> {
+    let lab = Lab::from_color_unclamped(Lch::<D50>::new(l, chroma, hue));
+    xyz_from_lab(lab.l, lab.a, lab.b, white_point)
+}
+
+#[test]
+fn test_cie_round_trip() {
+    let close = |actual: (f32, f32, f32), expected: (f32, f32, f32)| {
+        (actual.0 - expected.0).abs() < 0.05
+            && (actual.1 - expected.1).abs() < 0.05
+            && (actual.2 - expected.2).abs() < 0.05
+    };
+    // sRGB red
+    let red = Xyz::new(0.4124, 0.2126, 0.0193);
+    // Reference values from the CSS Color 4 sample code
+    assert!(close(
+        lab_from_xyz(red, WhitePoint::D50),
+        (54.29, 80.80, 69.89)
+    ));
+    assert!(close(
+        lab_from_xyz(red, WhitePoint::D65),
+        (53.24, 80.09, 67.20)
+    ));
+    assert!(close(
+        xyz_components(red, WhitePoint::D50),
+        (0.4361, 0.2225, 0.0139)
+    ));
+    // White is the reference white, without chroma
+    let white = Xyz::new(0.95047, 1.0, 1.08883);
+    let (l, c, _) = lch_from_xyz(white, WhitePoint::D50);
+    assert!((l - 100.0).abs() < 0.01 && c < 0.01);
+
+    let xyz_close = |actual: Xyz<D65>, expected: Xyz<D65>| {
+        let (x, y, z) = actual.into_components();
+        let (ex, ey, ez) = expected.into_components();
+        (x - ex).abs() < 0.0001 && (y - ey).abs() < 0.0001 && (z - ez).abs() < 0.0001
+    };
+    for white_point in [WhitePoint::D50, WhitePoint::D65] {
+        // A Rec.2020 green, outside of sRGB, goes through unclamped
+        for xyz in [
+            red,
+            white,
+            Xyz::new(0.1446, 0.678, 0.0281),
+            Xyz::new(0.0, 0.0, 0.0),
+        ] {
+            let (l, a, b) = lab_from_xyz(xyz, white_point);
+            assert!(xyz_close(xyz_from_lab(l, a, b, white_point), xyz));
+            let (l, c, h) = lch_from_xyz(xyz, white_point);
+            assert!(xyz_close(xyz_from_lch(l, c, h, white_point), xyz));
+            let (x, y, z) = xyz_components(xyz, white_point);
+            assert!(xyz_close(xyz_from_components(x, y, z, white_point), xyz));
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use crate::cie::WhitePoint;
+use crate::clipboard::selection::Selection;
+use crate::color_space::{ColorSettings, GamutMapping, MAX_PRECISION, WorkingSpace};
+use crate::commands::parse_step;
+use crate::constants::{
+    IMAGE_VIEW_MAX_SAMPLE_RADIUS, INPUTS_DEFAULT_BIG_STEP, INPUTS_DEFAULT_STEP, INPUTS_MIN_STEP,
+    PALETTE_CAPACITY,
+};
+use crate::extract::OutputFormat;
+use crate::scheme::SchemeFormat;
+use crate::utils::wal_colors_path;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: tui-color-picker [OPTIONS] [IMAGE]
+       tui-color-picker extract [EXTRACT OPTIONS] <IMAGE>
+       tui-color-picker export [EXPORT OPTIONS] <FORMAT>
+       tui-color-picker scan [SCAN OPTIONS] [FILE]...
+
+Arguments:
+  [IMAGE]  PNG, JPEG or PPM image to sample colors from
+
+Options:
+  --selection <clipboard|primary|both>  Selection used by copy and paste
+  --sample-radius <N>                   Average image samples over N pixels around the click
+  --palette <FILE>                      Import a base16/base24 scheme, pywal colors.json or
+                                        Xresources file as an editable palette
+  --from-wal                            Import the pywal or wallust colors from ~/.cache/wal
+  --listen <SOCKET>                     Accept JSON-lines requests on a Unix socket
+  --edit <FILE:LINE[:COL]>              Edit the color literal at that position of the file
+  --white-point <d50|d65>               Reference white of the Lab, LCh and XYZ values
+                                        [default: d50]
+  --space <srgb|display-p3|rec2020>     RGB space of the picker and of the RGB/HSV inputs
+                                        [default: srgb]
+  --gamut-map <clip|css>                Clip colors converted to sRGB, or reduce their chroma
+                                        as CSS Color 4 does [default: clip]
+  --precision <0-3>                     Decimals of the copied values
+  --step <N>                            Step of Up/Down in the input fields [default: 1]
+  --big-step <N>                        Step of PageUp/PageDown in the input fields [default: 10]
+  -h, --help                            Print this help
+
+Commands:
+  extract  Print the dominant colors of an image
+  export   Print the palette of the terminal as a color scheme
+  scan     Print the color literals of files, or of the standard input
+
+Extract options:
+  -n, --count <N>           Number of colors to extract [default: 8]
+  --seed <SEED>             Seed of the clustering initialization [default: 0]
+  --format <text|json|gpl>  Output format [default: text]
+
+Export options:
+  <FORMAT>             alacritty, kitty, wezterm, foot, windows-terminal, xresources or sh
+  -o, --output <PATH>  Write the scheme to PATH instead of the standard output
+  --name <NAME>        Name of the scheme [default: the output file name or terminal]
+
+Scan options:
+  [FILE]...                 Files to scan, - or nothing for the standard input
+  --format <text|json|gpl>  Output format [default: text]
+";
+
+pub enum Command {
+    Pick(Args),
+    Extract(ExtractArgs),
+    Export(ExportArgs),
+    Scan(ScanArgs),
+}
+
+pub struct Args {
+    pub selection: Selection,
+    pub image: Option<PathBuf>,
+    pub sample_radius: u32,
+    pub palette: Option<PathBuf>,
+    pub listen: Option<PathBuf>,
+    pub edit: Option<String>,
+    pub color_settings: ColorSettings,
+    pub step: f32,
+    pub big_step: f32,
+}
+
+pub struct ExtractArgs {
+    pub image: PathBuf,
+    pub count: usize,
+    pub seed: u64,
+    pub format: OutputFormat,
+}
+
+pub struct ExportArgs {
+    pub format: SchemeFormat,
+    pub output: Option<PathBuf>,
+    pub name: Option<String>,
+}
+
+pub struct ScanArgs {
+    pub files: Vec<PathBuf>,
+    pub format: OutputFormat,
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    args.next().ok_or(format!("{} requires a value", option))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {}: {}", what, value))
+}
+
+/// Parses the command line arguments, `args` should not contain the program
+/// name. Returns the error message to print along the usage on failure, an
+/// empty message means the help was requested.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(String::as_str) == Some("extract") {
+        args.next();
+        return parse_extract_args(args).map(Command::Extract);
+    }
+    if args.peek().map(String::as_str) == Some("export") {
+        args.next();
+        return parse_export_args(args).map(Command::Export);
+    }
+    if args.peek().map(String::as_str) == Some("scan") {
+        args.next();
+        return parse_scan_args(args).map(Command::Scan);
+    }
+
+    let mut parsed = Args {
+        selection: Selection::Clipboard,
+        image: None,
+        sample_radius: 0,
+        palette: None,
+        listen: None,
+        edit: None,
+        color_settings: ColorSettings::default(),
+        step: INPUTS_DEFAULT_STEP,
+        big_step: INPUTS_DEFAULT_BIG_STEP,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--selection" => {
+                let value = next_value(&mut args, &arg)?;
+                parsed.selection =
+                    Selection::parse(&value).ok_or(format!("invalid selection: {}", value))?;
+            }
+            "--sample-radius" => {
+                let radius = parse_number(&next_value(&mut args, &arg)?, "sample radius")?;
+                if radius > IMAGE_VIEW_MAX_SAMPLE_RADIUS {
+                    return Err(format!(
+                        "sample radius must be at most {}",
+                        IMAGE_VIEW_MAX_SAMPLE_RADIUS
+                    ));
+                }
+                parsed.sample_radius = radius;
+            }
+            "--palette" => parsed.palette = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+            "--listen" => parsed.listen = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+            "--edit" => parsed.edit = Some(next_value(&mut args, &arg)?),
+            "--white-point" => {
+                let value = next_value(&mut args, &arg)?;
+                parsed.color_settings.white_point =
+                    WhitePoint::parse(&value).ok_or(format!("invalid white point: {}", value))?;
+            }
+            "--space" => {
+                let value = next_value(&mut args, &arg)?;
+                parsed.color_settings.space =
+                    WorkingSpace::parse(&value).ok_or(format!("invalid color space: {}", value))?;
+            }
+            "--gamut-map" => {
+                let value = next_value(&mut args, &arg)?;
+                parsed.color_settings.gamut_mapping = GamutMapping::parse(&value)
+                    .ok_or(format!("invalid gamut mapping: {}", value))?;
+            }
+            "--precision" => {
+                let precision = parse_number(&next_value(&mut args, &arg)?, "precision")?;
+                if precision > MAX_PRECISION {
+                    return Err(format!("precision must be at most {}", MAX_PRECISION));
+                }
+                parsed.color_settings.precision = Some(precision);
+            }
+            "--step" | "--big-step" => {
+                let value = next_value(&mut args, &arg)?;
+                let step = parse_step(&value).ok_or(format!(
+                    "step must be at least {}: {}",
+                    INPUTS_MIN_STEP, value
+                ))?;
+                if arg == "--step" {
+                    parsed.step = step;
+                } else {
+                    parsed.big_step = step;
+                }
+            }
+            "--from-wal" => {
+                parsed.palette =
+                    Some(wal_colors_path().ok_or("no cache directory for --from-wal".to_string())?)
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("unknown argument: {}", arg)),
+            _ if parsed.image.is_none() => parsed.image = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    Ok(Command::Pick(parsed))
+}
+
+fn parse_extract_args(mut args: impl Iterator<Item = String>) -> Result<ExtractArgs, String> {
+    let mut image = None;
+    let mut parsed = ExtractArgs {
+        image: PathBuf::new(),
+        count: 8,
+        seed: 0,
+        format: OutputFormat::Text,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-n" | "--count" => {
+                let count = parse_number(&next_value(&mut args, &arg)?, "count")?;
+                if count > PALETTE_CAPACITY {
+                    return Err(format!("count must be at most {}", PALETTE_CAPACITY));
+                }
+                parsed.count = count;
+            }
+            "--seed" => parsed.seed = parse_number(&next_value(&mut args, &arg)?, "seed")?,
+            "--format" => {
+                let value = next_value(&mut args, &arg)?;
+                parsed.format =
+                    OutputFormat::parse(&value).ok_or(format!("invalid format: {}", value))?;
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("unknown argument: {}", arg)),
+            _ if image.is_none() => image = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    parsed.image = image.ok_or("extract requires an image".to_string())?;
+    Ok(parsed)
+}
+
+fn parse_export_args(mut args: impl Iterator<Item = String>) -> Result<ExportArgs, String> {
+    let mut format = None;
+    let mut parsed = ExportArgs {
+        format: SchemeFormat::Shell,
+        output: None,
+        name: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => parsed.output = Some(PathBuf::from(next_value(&mut args, &arg)?)),
+            "--name" => parsed.name = Some(next_value(&mut args, &arg)?),
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("unknown argument: {}", arg)),
+            _ if format.is_none() => {
+                format = Some(SchemeFormat::parse(&arg).ok_or(format!("invalid format: {}", arg))?)
+            }
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    parsed.format = format.ok_or("export requires a format".to_string())?;
+    Ok(parsed)
+}
+
+fn parse_scan_args(mut args: impl Iterator<Item = String>) -> Result<ScanArgs, String> {
+    let mut parsed = ScanArgs {
+        files: Vec::new(),
+        format: OutputFormat::Text,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                let value = next_value(&mut args, &arg)?;
+                parsed.format =
+                    OutputFormat::parse(&value).ok_or(format!("invalid format: {}", value))?;
+            }
+            "-h" | "--help" => return Err(String::new()),
+            "-" => parsed.files.push(PathBuf::from(arg)),
+            _ if arg.starts_with('-') => return Err(format!("unknown argument: {}", arg)),
+            _ => parsed.files.push(PathBuf::from(arg)),
+        }
+    }
+    Ok(parsed)
+}
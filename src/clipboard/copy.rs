@@ -1,3 +1,4 @@
+use crate::clipboard::selection::Selection;
 use arboard::Clipboard;
 use std::{
     io::{self},
@@ -6,18 +7,54 @@ use std::{
 
 /// Warning no input validation is done on args inside of this
 /// function - be careful when using user input.
-pub fn wl_copy(arg: &str) -> io::Result<std::process::Child> {
-    Command::new("wl-copy").arg(arg).spawn()
+pub fn wl_copy(arg: &str, primary: bool) -> io::Result<std::process::Child> {
+    let mut command = Command::new("wl-copy");
+    if primary {
+        command.arg("--primary");
+    }
+    command.arg(arg).spawn()
 }
 
 // We have to use a special method to copy to clipboard on Wayland because arboard
 // will not write to wl-copy so when the prgram is closed, the clipboard is lost.
-pub fn clipboard_copy(str: &str) -> io::Result<()> {
+pub fn clipboard_copy(str: &str, selection: Selection) -> io::Result<()> {
     if std::env::var("WAYLAND_DISPLAY").is_ok() {
-        wl_copy(str)?;
+        if selection.includes_clipboard() {
+            wl_copy(str, false)?;
+        }
+        if selection.includes_primary() {
+            wl_copy(str, true)?;
+        }
     } else {
-        let mut clipboard = Clipboard::new().unwrap();
-        clipboard.set_text(str).unwrap();
+        let mut clipboard = Clipboard::new().map_err(io::Error::other)?;
+        if selection.includes_clipboard() {
+            clipboard.set_text(str).map_err(io::Error::other)?;
+        }
+        if selection.includes_primary() {
+            set_primary_text(&mut clipboard, str)?;
+        }
     }
     Ok(())
 }
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+fn set_primary_text(clipboard: &mut Clipboard, str: &str) -> io::Result<()> {
+    use arboard::{LinuxClipboardKind, SetExtLinux};
+    clipboard
+        .set()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text(str)
+        .map_err(io::Error::other)
+}
+
+// No primary selection outside of X11/Wayland, fall back to the clipboard.
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+)))]
+fn set_primary_text(clipboard: &mut Clipboard, str: &str) -> io::Result<()> {
+    clipboard.set_text(str).map_err(io::Error::other)
+}
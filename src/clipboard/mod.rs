@@ -1,3 +1,4 @@
 pub mod copy;
 pub mod paste;
+pub mod selection;
 pub mod ui;
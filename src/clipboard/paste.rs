@@ -1,13 +1,56 @@
 use arboard::Clipboard;
-use palette::Hsv;
+use palette::white_point::D65;
+use palette::{FromColor, Hsv, Srgb, Xyz};
 use regex::Regex;
+use std::io;
 
-use crate::utils::hsv_from_rgb;
+use crate::cie::{WhitePoint, xyz_from_components, xyz_from_lab, xyz_from_lch};
+use crate::clipboard::selection::Selection;
+use crate::color_space::{ColorSettings, WorkingSpace, srgb_from_xyz};
+use crate::utils::{hsv_from_cmyk, hsv_from_rgb};
 
-pub fn clipboard_paste() -> Option<Hsv> {
-    let mut clipboard = Clipboard::new().unwrap();
-    let content = clipboard.get_text().unwrap();
-    validate_pasted_color(&content)
+/// Reads a color from the given selection, in the working space of
+/// `settings`. With `Selection::Both`, the clipboard is tried first and the
+/// primary selection is used as a fallback. Returns `Ok(None)` if the
+/// selection does not contain a color.
+pub fn clipboard_paste(selection: Selection, settings: &ColorSettings) -> io::Result<Option<Hsv>> {
+    let mut clipboard = Clipboard::new().map_err(io::Error::other)?;
+    if selection.includes_clipboard()
+        && let Some(hsv) = clipboard
+            .get_text()
+            .ok()
+            .and_then(|content| validate_pasted_color_in(content.trim(), settings))
+    {
+        return Ok(Some(hsv));
+    }
+    if selection.includes_primary()
+        && let Some(content) = get_primary_text(&mut clipboard)
+    {
+        return Ok(validate_pasted_color_in(content.trim(), settings));
+    }
+    Ok(None)
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+fn get_primary_text(clipboard: &mut Clipboard) -> Option<String> {
+    use arboard::{GetExtLinux, LinuxClipboardKind};
+    clipboard
+        .get()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text()
+        .ok()
+}
+
+// No primary selection outside of X11/Wayland, fall back to the clipboard.
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+)))]
+fn get_primary_text(clipboard: &mut Clipboard) -> Option<String> {
+    clipboard.get_text().ok()
 }
 
 fn validate_rgb(s: &str) -> Option<Hsv> {
@@ -46,6 +89,127 @@ fn validate_hsv(s: &str) -> Option<Hsv> {
     Some(Hsv::new(h, s, v))
 }
 
+/// `cmyk(c, m, y, k)` in percents, or in fractions like HSV. The four inks
+/// share a scale: any `%` or value above 1 makes them all percents.
+fn validate_cmyk(s: &str) -> Option<Hsv> {
+    static RE: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+        Regex::new(
+            r"(?i)^cmyk\(\s*(\d{1,3}(?:\.\d+)?%?)\s*,\s*(\d{1,3}(?:\.\d+)?%?)\s*,\s*(\d{1,3}(?:\.\d+)?%?)\s*,\s*(\d{1,3}(?:\.\d+)?%?)\s*\)$",
+        )
+        .unwrap()
+    });
+    let cap = RE.captures(s)?;
+    let numbers: Vec<&str> = cap
+        .iter()
+        .skip(1)
+        .map(|number| number.unwrap().as_str())
+        .collect();
+    let mut inks = [0.0; 4];
+    for (ink, number) in inks.iter_mut().zip(&numbers) {
+        *ink = number.trim_end_matches('%').parse().ok()?;
+    }
+    let percent =
+        numbers.iter().any(|number| number.ends_with('%')) || inks.iter().any(|ink| *ink > 1.0);
+    if percent {
+        inks = inks.map(|ink| ink / 100.0);
+    }
+    if inks.iter().any(|ink| *ink > 1.0) {
+        return None;
+    }
+    let [c, m, y, k] = inks;
+    Some(hsv_from_cmyk(c, m, y, k))
+}
+
+/// Number of a CSS color function, percentages are scaled so that 100% is
+/// `percent_scale`.
+fn css_number(number: &str, percent_scale: f32) -> Option<f32> {
+    match number.strip_suffix('%') {
+        Some(percent) => Some(percent.parse::<f32>().ok()? / 100.0 * percent_scale),
+        None => number.trim_end_matches("deg").parse().ok(),
+    }
+}
+
+/// CSS `lab(L a b)`, which is relative to D50
+fn validate_lab(s: &str) -> Option<Xyz<D65>> {
+    static RE: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+        Regex::new(r"(?i)^lab\(\s*(\d*\.?\d+%?)[\s,]+(-?\d*\.?\d+%?)[\s,]+(-?\d*\.?\d+%?)\s*\)$")
+            .unwrap()
+    });
+    let cap = RE.captures(s)?;
+    let l = css_number(&cap[1], 100.0)?;
+    let a = css_number(&cap[2], 125.0)?;
+    let b = css_number(&cap[3], 125.0)?;
+    if l > 100.0 {
+        return None;
+    }
+    Some(xyz_from_lab(l, a, b, WhitePoint::D50))
+}
+
+/// CSS `lch(L C h)`, which is relative to D50
+fn validate_lch(s: &str) -> Option<Xyz<D65>> {
+    static RE: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+        Regex::new(
+            r"(?i)^lch\(\s*(\d*\.?\d+%?)[\s,]+(\d*\.?\d+%?)[\s,]+(-?\d*\.?\d+(?:deg)?)\s*\)$",
+        )
+        .unwrap()
+    });
+    let cap = RE.captures(s)?;
+    let l = css_number(&cap[1], 100.0)?;
+    let c = css_number(&cap[2], 150.0)?;
+    let h = css_number(&cap[3].to_ascii_lowercase(), 360.0)?;
+    if l > 100.0 {
+        return None;
+    }
+    Some(xyz_from_lch(l, c, h, WhitePoint::D50))
+}
+
+/// CSS `color(xyz x y z)`, `xyz` being `xyz-d65`, or `color(xyz-d50 x y z)`
+fn validate_xyz(s: &str) -> Option<Xyz<D65>> {
+    static RE: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+        Regex::new(
+            r"(?i)^color\(\s*xyz(-d50|-d65)?\s+(-?\d*\.?\d+%?)[\s,]+(-?\d*\.?\d+%?)[\s,]+(-?\d*\.?\d+%?)\s*\)$",
+        )
+        .unwrap()
+    });
+    let cap = RE.captures(s)?;
+    let white_point = match cap.get(1) {
+        Some(suffix) => WhitePoint::parse(&suffix.as_str()[1..])?,
+        None => WhitePoint::D65,
+    };
+    let x = css_number(&cap[2], 1.0)?;
+    let y = css_number(&cap[3], 1.0)?;
+    let z = css_number(&cap[4], 1.0)?;
+    Some(xyz_from_components(x, y, z, white_point))
+}
+
+/// CSS `color(srgb r g b)`, `color(display-p3 r g b)` or
+/// `color(rec2020 r g b)`, with channels from 0 to 1
+fn validate_color_function(s: &str) -> Option<Xyz<D65>> {
+    static RE: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+        Regex::new(
+            r"(?i)^color\(\s*(srgb|display-p3|rec2020)\s+(\d*\.?\d+%?)[\s,]+(\d*\.?\d+%?)[\s,]+(\d*\.?\d+%?)\s*\)$",
+        )
+        .unwrap()
+    });
+    let cap = RE.captures(s)?;
+    let space = WorkingSpace::parse(&cap[1])?;
+    let r = css_number(&cap[2], 1.0)?;
+    let g = css_number(&cap[3], 1.0)?;
+    let b = css_number(&cap[4], 1.0)?;
+    if [r, g, b].iter().any(|channel| *channel > 1.0) {
+        return None;
+    }
+    Some(space.to_xyz(&Hsv::from_color(Srgb::new(r, g, b))))
+}
+
+/// Notations that can be outside of sRGB
+fn validate_wide_color(s: &str) -> Option<Xyz<D65>> {
+    validate_lab(s)
+        .or_else(|| validate_lch(s))
+        .or_else(|| validate_xyz(s))
+        .or_else(|| validate_color_function(s))
+}
+
 fn validate_hex(s: &str) -> Option<Hsv> {
     static RE: once_cell::sync::Lazy<Regex> =
         once_cell::sync::Lazy::new(|| Regex::new(r"^(?:0?[xX]|#)?([0-9a-fA-F]{6})$").unwrap());
@@ -68,7 +232,19 @@ pub fn validate_pasted_color(str: &str) -> Option<Hsv> {
     if let Some(hsv) = validate_hsv(str) {
         return Some(hsv);
     }
-    None
+    if let Some(hsv) = validate_cmyk(str) {
+        return Some(hsv);
+    }
+    validate_wide_color(str).map(srgb_from_xyz)
+}
+
+/// Like `validate_pasted_color`, but the color is in the working space of
+/// `settings`. Wide gamut notations are converted without going through sRGB.
+pub fn validate_pasted_color_in(str: &str, settings: &ColorSettings) -> Option<Hsv> {
+    match validate_wide_color(str) {
+        Some(xyz) => Some(settings.from_xyz(xyz)),
+        None => validate_pasted_color(str).map(|color| settings.from_srgb(&color)),
+    }
 }
 
 #[test]
@@ -143,6 +319,95 @@ fn test_paste_validation() {
             input: "hsv(11.00, 0.8, 1.0)",
             expected: Some(Hsv::new(11.0, 0.8, 1.0)),
         },
+        TestCase {
+            input: "cmyk(0%, 66%, 80%, 0%)",
+            expected: Some(hsv_from_cmyk(0.0, 0.66, 0.8, 0.0)),
+        },
+        TestCase {
+            input: "CMYK( 0 , 0.66 , 0.8 , 0 )",
+            expected: Some(hsv_from_cmyk(0.0, 0.66, 0.8, 0.0)),
+        },
+        TestCase {
+            input: "cmyk(0, 0, 0, 100)",
+            expected: Some(hsv_from_cmyk(0.0, 0.0, 0.0, 1.0)),
+        },
+        TestCase {
+            input: "cmyk(50, 1, 0, 0)",
+            expected: Some(hsv_from_cmyk(0.5, 0.01, 0.0, 0.0)),
+        },
+        TestCase {
+            input: "cmyk(0.5, 1, 0, 0)",
+            expected: Some(hsv_from_cmyk(0.5, 1.0, 0.0, 0.0)),
+        },
+        TestCase {
+            input: "cmyk(0%, 1, 0, 0)",
+            expected: Some(hsv_from_cmyk(0.0, 0.01, 0.0, 0.0)),
+        },
+        TestCase {
+            input: "cmyk(0%, 120%, 0%, 0%)",
+            expected: None,
+        },
+        TestCase {
+            input: "cmyk(0%, 66%, 80%)",
+            expected: None,
+        },
+        TestCase {
+            input: "lab(54.29 80.81 69.89)",
+            expected: Some(srgb_from_xyz(xyz_from_lab(
+                54.29,
+                80.81,
+                69.89,
+                WhitePoint::D50,
+            ))),
+        },
+        TestCase {
+            input: "LAB(54.29%, 80.81, -50%)",
+            expected: Some(srgb_from_xyz(xyz_from_lab(
+                54.29,
+                80.81,
+                -62.5,
+                WhitePoint::D50,
+            ))),
+        },
+        TestCase {
+            input: "lch(54.29 106.84 40.85deg)",
+            expected: Some(srgb_from_xyz(xyz_from_lch(
+                54.29,
+                106.84,
+                40.85,
+                WhitePoint::D50,
+            ))),
+        },
+        TestCase {
+            input: "color(xyz 0.4124 0.2126 0.0193)",
+            expected: Some(srgb_from_xyz(xyz_from_components(
+                0.4124,
+                0.2126,
+                0.0193,
+                WhitePoint::D65,
+            ))),
+        },
+        TestCase {
+            input: "color(xyz-d50 0.4361 0.2225 0.0139)",
+            expected: Some(srgb_from_xyz(xyz_from_components(
+                0.4361,
+                0.2225,
+                0.0139,
+                WhitePoint::D50,
+            ))),
+        },
+        TestCase {
+            input: "lab(120 0 0)",
+            expected: None,
+        },
+        TestCase {
+            input: "color(srgb 1.2 0 0)",
+            expected: None,
+        },
+        TestCase {
+            input: "color(prophoto-rgb 1 0 0)",
+            expected: None,
+        },
         TestCase {
             input: "invalid string",
             expected: None,
@@ -196,4 +461,28 @@ fn test_paste_validation() {
             case.input
         );
     }
+
+    // color() keeps colors outside of sRGB in a wider working space
+    use crate::utils::rgb_from_hsv;
+    let p3 = ColorSettings {
+        space: WorkingSpace::DisplayP3,
+        ..Default::default()
+    };
+    let pasted = |input: &str, settings: &ColorSettings| {
+        validate_pasted_color_in(input, settings).map(|color| rgb_from_hsv(&color))
+    };
+    assert_eq!(pasted("color(display-p3 1 0 0)", &p3), Some((255, 0, 0)));
+    assert_eq!(
+        pasted("COLOR(srgb 100% 34.1% 20%)", &p3),
+        Some((236, 99, 66))
+    );
+    assert_eq!(pasted("#FF5733", &p3), Some((236, 99, 66)));
+    assert_eq!(
+        pasted("color(srgb 1 0.341 0.2)", &ColorSettings::default()),
+        Some((255, 87, 51))
+    );
+    assert_eq!(
+        validate_pasted_color("color(display-p3 1 0 0)").map(|color| rgb_from_hsv(&color)),
+        Some((255, 0, 0))
+    );
 }
@@ -0,0 +1,35 @@
+/// Which X11/Wayland selection a clipboard operation targets. On platforms
+/// without a primary selection, `Primary` and `Both` behave like `Clipboard`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Clipboard,
+    Primary,
+    Both,
+}
+
+impl Selection {
+    pub fn parse(s: &str) -> Option<Selection> {
+        match s.to_ascii_lowercase().as_str() {
+            "clipboard" => Some(Selection::Clipboard),
+            "primary" => Some(Selection::Primary),
+            "both" => Some(Selection::Both),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Selection::Clipboard => "clipboard",
+            Selection::Primary => "primary selection",
+            Selection::Both => "clipboard and primary",
+        }
+    }
+
+    pub fn includes_clipboard(&self) -> bool {
+        *self != Selection::Primary
+    }
+
+    pub fn includes_primary(&self) -> bool {
+        *self != Selection::Clipboard
+    }
+}
@@ -1,33 +1,57 @@
 use crate::clipboard::copy::clipboard_copy;
-use std::io::{self, stdout};
+use crate::clipboard::selection::Selection;
+use std::io::{self, Write};
 
 use crossterm::{
     cursor::{MoveDown, MoveLeft, MoveRight, MoveTo},
-    event::{KeyCode, KeyEvent},
-    execute,
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    queue,
     style::Print,
 };
-use palette::Hsv;
+use palette::{FromColor, Hsv, Srgb};
 
 use crate::{
+    color_space::ColorSettings,
     constants::*,
     crossterm_commands::{FillRect, PrintBold, PrintBoldColored, ResetDefaultColors},
     types::Vec2,
-    utils::rgb_from_hsv,
+    utils::{cmyk_from_hsv, format_decimal, rgb_from_hsv},
 };
 
 pub enum ColorFormat {
     Hex,
     Rgb,
     Hsv,
+    Cmyk,
+    Lab,
+    Lch,
+    Xyz,
+    /// CSS `color()` in the working space
+    Color,
 }
 
 impl ColorFormat {
+    pub const ALL: [ColorFormat; 8] = [
+        ColorFormat::Hex,
+        ColorFormat::Rgb,
+        ColorFormat::Hsv,
+        ColorFormat::Cmyk,
+        ColorFormat::Lab,
+        ColorFormat::Lch,
+        ColorFormat::Xyz,
+        ColorFormat::Color,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             ColorFormat::Hex => "Hex",
             ColorFormat::Rgb => "RGB",
             ColorFormat::Hsv => "HSV",
+            ColorFormat::Cmyk => "CMYK",
+            ColorFormat::Lab => "Lab",
+            ColorFormat::Lch => "LCh",
+            ColorFormat::Xyz => "XYZ",
+            ColorFormat::Color => "Color",
         }
     }
     pub fn title(&self) -> &'static str {
@@ -35,6 +59,11 @@ impl ColorFormat {
             ColorFormat::Hex => "He[x]",
             ColorFormat::Rgb => "[R]GB",
             ColorFormat::Hsv => "[H]SV",
+            ColorFormat::Cmyk => "[C]MYK",
+            ColorFormat::Lab => "L[a]b",
+            ColorFormat::Lch => "[L]Ch",
+            ColorFormat::Xyz => "XY[Z]",
+            ColorFormat::Color => "C[o]lor",
         }
     }
     pub fn as_char(&self) -> char {
@@ -42,13 +71,93 @@ impl ColorFormat {
             ColorFormat::Hex => 'x',
             ColorFormat::Rgb => 'r',
             ColorFormat::Hsv => 'h',
+            ColorFormat::Cmyk => 'c',
+            ColorFormat::Lab => 'a',
+            ColorFormat::Lch => 'l',
+            ColorFormat::Xyz => 'z',
+            ColorFormat::Color => 'o',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<ColorFormat> {
+        ColorFormat::ALL
+            .into_iter()
+            .find(|format| format.as_char() == c)
+    }
+
+    /// The color, in the working space, as copied in this format. Hex, RGB,
+    /// HSV and CMYK are sRGB and gamut mapped. `lab()` and `lch()` are CSS
+    /// colors and always relative to D50, XYZ is written for the white point.
+    pub fn format(&self, color: &Hsv, settings: &ColorSettings) -> String {
+        let srgb = settings.srgb(color);
+        let (r, g, b) = rgb_from_hsv(&srgb);
+        let decimals = |value: f32, default: usize, extra: usize| match settings.precision {
+            Some(precision) => format_decimal(value, precision + extra),
+            None => format!("{:.*}", default, value),
+        };
+        let number = |value: f32| decimals(value, 2, 0);
+        let percent = |value: f32| decimals(value, 0, 0);
+        // A hue that rounds to 360 is written as 0
+        let hue = |text: String| match text.parse::<f32>() {
+            Ok(degrees) if degrees >= 360.0 => text.replacen("360", "0", 1),
+            _ => text,
+        };
+        // XYZ and color() channels are from 0 to 1
+        let fraction = |value: f32| decimals(value, 3, 1);
+        match self {
+            ColorFormat::Hex => format!("#{:02X}{:02X}{:02X}", r, g, b),
+            ColorFormat::Rgb => format!("rgb({}, {}, {})", r, g, b),
+            ColorFormat::Hsv => format!(
+                "hsv({}, {}, {})",
+                hue(percent(srgb.hue.into_positive_degrees())),
+                percent(srgb.saturation * 100.0),
+                percent(srgb.value * 100.0)
+            ),
+            ColorFormat::Cmyk => {
+                let (c, m, y, k) = cmyk_from_hsv(&srgb);
+                format!(
+                    "cmyk({}%, {}%, {}%, {}%)",
+                    percent(c * 100.0),
+                    percent(m * 100.0),
+                    percent(y * 100.0),
+                    percent(k * 100.0)
+                )
+            }
+            ColorFormat::Lab => {
+                let (l, a, b) = settings.css().lab(color);
+                format!("lab({} {} {})", number(l), number(a), number(b))
+            }
+            ColorFormat::Lch => {
+                let (l, c, h) = settings.css().lch(color);
+                format!("lch({} {} {})", number(l), number(c), hue(number(h)))
+            }
+            ColorFormat::Xyz => {
+                let (x, y, z) = settings.xyz(color);
+                format!(
+                    "color(xyz-{} {} {} {})",
+                    settings.white_point.name().to_ascii_lowercase(),
+                    fraction(x),
+                    fraction(y),
+                    fraction(z)
+                )
+            }
+            ColorFormat::Color => {
+                let rgb = Srgb::from_color(*color);
+                format!(
+                    "color({} {} {} {})",
+                    settings.space.css_name(),
+                    fraction(rgb.red),
+                    fraction(rgb.green),
+                    fraction(rgb.blue)
+                )
+            }
         }
     }
 }
 
-pub fn clear_clipboard_format_selector(pos: Vec2) -> io::Result<()> {
-    execute!(
-        stdout(),
+pub fn clear_clipboard_format_selector(out: &mut impl Write, pos: Vec2) -> io::Result<()> {
+    queue!(
+        out,
         ResetDefaultColors(false),
         FillRect(
             &pos,
@@ -58,96 +167,165 @@ pub fn clear_clipboard_format_selector(pos: Vec2) -> io::Result<()> {
     )
 }
 
-pub fn draw_copied_confirmation(pos: Vec2, fade: bool) -> io::Result<()> {
-    const CONFIRMATION_TEXT: &str = "Copied to clipboard!";
-    execute!(
-        stdout(),
-        ResetDefaultColors(fade),
-        MoveTo(pos.x as u16, pos.y as u16),
-        PrintBold(CONFIRMATION_TEXT),
-    )
-}
-
-pub fn draw_pasted_confirmation(pos: Vec2, fade: bool) -> io::Result<()> {
-    const CONFIRMATION_TEXT: &str = "Pasted from clipboard!";
-    execute!(
-        stdout(),
-        ResetDefaultColors(fade),
-        MoveTo(pos.x as u16, pos.y as u16),
-        PrintBold(CONFIRMATION_TEXT),
-    )
+/// Cuts `text` with an ellipsis to the width left after the labels
+fn fit_text(text: String) -> String {
+    let width = (COPY_FORMAT_SELECTOR_WIDTH - COPY_FORMAT_SELECTOR_SPACING) as usize;
+    if text.chars().count() <= width {
+        return text;
+    }
+    let mut text: String = text.chars().take(width - 1).collect();
+    text.push('\u{2026}');
+    text
 }
 
-pub fn draw_clipboard_format_selector(pos: Vec2, mut color: Hsv, fade: bool) -> io::Result<()> {
+pub fn draw_clipboard_format_selector(
+    out: &mut impl Write,
+    pos: Vec2,
+    color: Hsv,
+    settings: &ColorSettings,
+    fade: bool,
+) -> io::Result<()> {
     const TITLE: &str = "Select Copy Format:";
+    const MODIFIERS_HINT: &str = "Shift=primary Alt=both";
 
-    let (r, g, b) = rgb_from_hsv(&color);
-    let hex_str = format!("#{:02X}{:02X}{:02X}", r, g, b);
-    let rgb_str = format!("rgb({}, {}, {})", r, g, b);
-    let hsv_str = format!(
-        "hsv({}, {}, {})",
-        color.hue.into_positive_degrees() as u8,
-        (color.saturation * 100.0) as u8,
-        (color.value * 100.0) as u8
-    );
+    let texts = ColorFormat::ALL.map(|format| fit_text(format.format(&color, settings)));
 
     // Change color for display purposes
+    let mut color = settings.srgb(&color);
     color.saturation = 0.5;
     color.value = 0.95;
     let (r, g, b) = rgb_from_hsv(&color);
 
-    execute!(
-        stdout(),
+    queue!(
+        out,
         ResetDefaultColors(fade),
         MoveTo(pos.x as u16, pos.y as u16),
         PrintBold(TITLE),
         MoveLeft(TITLE.len() as u16),
         MoveDown(1),
-        Print("He"),
-        PrintBoldColored("x", r, g, b),
-        Print(":"),
-        MoveRight(COPY_FORMAT_SELECTOR_SPACING - 4),
-        Print(&hex_str),
-        MoveLeft(COPY_FORMAT_SELECTOR_SPACING + hex_str.len() as u16),
-        MoveDown(1),
-        PrintBoldColored("R", r, g, b),
-        Print("GB:"),
-        MoveRight(COPY_FORMAT_SELECTOR_SPACING - 4),
-        Print(&rgb_str),
-        MoveLeft(COPY_FORMAT_SELECTOR_SPACING + rgb_str.len() as u16),
-        MoveDown(1),
-        PrintBoldColored("H", r, g, b),
-        Print("SV:"),
-        MoveRight(COPY_FORMAT_SELECTOR_SPACING - 4),
-        Print(&hsv_str),
     )?;
-    Ok(())
+    for (format, text) in ColorFormat::ALL.iter().zip(&texts) {
+        // The key is between brackets in the title
+        let (before, rest) = format.title().split_once('[').unwrap_or_default();
+        let (key, after) = rest.split_once(']').unwrap_or_default();
+        let label_len = (format.as_str().len() + 1) as u16;
+        queue!(
+            out,
+            Print(before),
+            PrintBoldColored(key, r, g, b),
+            Print(after),
+            Print(":"),
+            MoveRight(COPY_FORMAT_SELECTOR_SPACING - label_len),
+            Print(text),
+            MoveLeft(COPY_FORMAT_SELECTOR_SPACING + text.chars().count() as u16),
+            MoveDown(1),
+        )?;
+    }
+    queue!(
+        out,
+        ResetDefaultColors(true),
+        Print(MODIFIERS_HINT),
+        ResetDefaultColors(fade),
+    )
 }
 
-pub fn handle_copy_input_format_selection_input(event: KeyEvent, color: Hsv) -> io::Result<bool> {
-    match event.code {
-        KeyCode::Char('x') => {
-            let (r, g, b) = rgb_from_hsv(&color);
-            let hex_str = format!("#{:02X}{:02X}{:02X}", r, g, b);
-            clipboard_copy(&hex_str)?;
-        }
-        KeyCode::Char('r') => {
-            let (r, g, b) = rgb_from_hsv(&color);
-            let rgb_str = format!("rgb({}, {}, {})", r, g, b);
-            clipboard_copy(&rgb_str)?;
-        }
-        KeyCode::Char('h') => {
-            let hsv_str = format!(
-                "hsv({}, {}, {})",
-                color.hue.into_positive_degrees() as u8,
-                (color.saturation * 100.0) as u8,
-                (color.value * 100.0) as u8
-            );
-            clipboard_copy(&hsv_str)?;
-        }
-        _ => {
-            return Ok(false);
-        }
+/// Copies the color in the format matching the pressed key. Shift targets the
+/// primary selection and Alt targets both selections, otherwise `selection`
+/// is used. Returns the copied text and the selection written to, or `None`
+/// if the key was not a format key.
+pub fn handle_copy_input_format_selection_input(
+    event: KeyEvent,
+    color: Hsv,
+    settings: &ColorSettings,
+    selection: Selection,
+) -> io::Result<Option<(String, Selection)>> {
+    let KeyCode::Char(c) = event.code else {
+        return Ok(None);
+    };
+    // Ctrl+C cancels, no format is copied with Ctrl
+    if event.modifiers.contains(KeyModifiers::CONTROL) {
+        return Ok(None);
+    }
+    let selection = if event.modifiers.contains(KeyModifiers::ALT) {
+        Selection::Both
+    } else if event.modifiers.contains(KeyModifiers::SHIFT) || c.is_ascii_uppercase() {
+        Selection::Primary
+    } else {
+        selection
+    };
+    let Some(format) = ColorFormat::from_char(c.to_ascii_lowercase()) else {
+        return Ok(None);
+    };
+    let text = format.format(&color, settings);
+    clipboard_copy(&text, selection)?;
+    Ok(Some((text, selection)))
+}
+
+#[test]
+fn test_color_formats() {
+    let color = Hsv::new(359.8, 0.8025, 1.0);
+    let settings = ColorSettings::default();
+    // The hue used to be truncated to a u8, and 359.8 rounds to 0
+    assert_eq!(
+        ColorFormat::Hsv.format(&color, &settings),
+        "hsv(0, 80, 100)"
+    );
+    assert_eq!(
+        ColorFormat::Cmyk.format(&color, &settings),
+        "cmyk(0%, 80%, 80%, 0%)"
+    );
+    assert_eq!(
+        ColorFormat::Color.format(&color, &settings),
+        "color(srgb 1.000 0.197 0.200)"
+    );
+    let precise = ColorSettings {
+        precision: Some(2),
+        ..settings
+    };
+    assert_eq!(
+        ColorFormat::Hsv.format(&color, &precise),
+        "hsv(359.8, 80.25, 100)"
+    );
+    let rounded = ColorSettings {
+        precision: Some(0),
+        ..settings
+    };
+    assert_eq!(ColorFormat::Hsv.format(&color, &rounded), "hsv(0, 80, 100)");
+    assert_eq!(
+        ColorFormat::Cmyk.format(&color, &rounded),
+        "cmyk(0%, 80%, 80%, 0%)"
+    );
+    let red = Hsv::new(0.0, 1.0, 1.0);
+    assert_eq!(
+        ColorFormat::Lab.format(&red, &settings),
+        "lab(54.29 80.81 69.88)"
+    );
+    assert_eq!(
+        ColorFormat::Color.format(&red, &rounded),
+        "color(srgb 1 0 0)"
+    );
+}
+
+#[test]
+fn test_fit_text() {
+    assert_eq!(fit_text("#ff5733".to_string()), "#ff5733");
+    let text = fit_text("color(display-p3 0.9175 0.2003 0.1386)".to_string());
+    assert_eq!(text, "color(display-p3 0\u{2026}");
+    assert_eq!(
+        text.chars().count() as u16,
+        COPY_FORMAT_SELECTOR_WIDTH - COPY_FORMAT_SELECTOR_SPACING
+    );
+}
+
+#[test]
+fn test_copy_format_ctrl() {
+    let color = Hsv::new(0.0, 1.0, 1.0);
+    let settings = ColorSettings::default();
+    for c in ['c', 'h', 'r'] {
+        let event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let copied =
+            handle_copy_input_format_selection_input(event, color, &settings, Selection::Clipboard)
+                .unwrap();
+        assert_eq!(copied, None);
     }
-    Ok(true)
 }
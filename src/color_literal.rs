@@ -0,0 +1,279 @@
+use crate::utils::{hsv_from_rgb, rgb_from_hsv};
+use palette::Hsv;
+use regex::{Captures, Regex};
+use std::ops::Range;
+
+/// Notation of a color literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiteralFormat {
+    Hex,
+    Rgb,
+    Hsv,
+}
+
+impl LiteralFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LiteralFormat::Hex => "hex",
+            LiteralFormat::Rgb => "rgb",
+            LiteralFormat::Hsv => "hsv",
+        }
+    }
+}
+
+/// Color written in a text, with the byte spans of the whole literal and of
+/// its numbers so it can be rewritten in the same notation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorLiteral {
+    pub span: Range<usize>,
+    pub format: LiteralFormat,
+    pub color: Hsv,
+    pub text: String,
+    /// Spans of the hex digits or of the three numbers, relative to `span`
+    parts: Vec<Range<usize>>,
+    /// Whether the saturation and value of an HSV literal are percentages
+    percent: [bool; 2],
+}
+
+static HEX: once_cell::sync::Lazy<Regex> =
+    once_cell::sync::Lazy::new(|| Regex::new(r"(?:#|\b0[xX])([0-9a-fA-F]{6})\b").unwrap());
+static RGB: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+    Regex::new(r"(?i)\brgb\(\s*(\d{1,3})\s*,\s*(\d{1,3})\s*,\s*(\d{1,3})\s*\)").unwrap()
+});
+static HSV: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+    Regex::new(
+        r"(?i)\bhsv\(\s*(\d{1,3}(?:\.\d+)?)\s*,\s*(\d{1,3}(?:\.\d+)?%?)\s*,\s*(\d{1,3}(?:\.\d+)?%?)\s*\)",
+    )
+    .unwrap()
+});
+/// Notations `validate_pasted_color` accepts that are too ambiguous to look
+/// for in any text, only tried at an explicit position.
+static BARE_HEX: once_cell::sync::Lazy<Regex> =
+    once_cell::sync::Lazy::new(|| Regex::new(r"\b[xX]?([0-9a-fA-F]{6})\b").unwrap());
+static TUPLE: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+    Regex::new(r"\(\s*(\d{1,3})\s*,\s*(\d{1,3})\s*,\s*(\d{1,3})\s*\)").unwrap()
+});
+
+fn decimals(number: &str) -> usize {
+    number
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len())
+}
+
+/// Units of the saturation and value of an HSV literal. A `%` always means a
+/// percentage. The numbers without one share a unit: percentages if one of
+/// them is above 1, or if they are integers next to a `%` one, fractions
+/// otherwise. `hsv(120, 1, 50)` is 1% saturated, `hsv(0, 0, 1)` is white.
+fn hsv_units(numbers: &[&str]) -> [bool; 2] {
+    let bare: Vec<&str> = numbers
+        .iter()
+        .copied()
+        .filter(|number| !number.ends_with('%'))
+        .collect();
+    let bare_percent = bare
+        .iter()
+        .any(|number| number.parse::<f32>().unwrap_or(0.0) > 1.0)
+        || (bare.len() < numbers.len() && !bare.iter().any(|number| number.contains('.')));
+    let unit = |number: &str| number.ends_with('%') || bare_percent;
+    [unit(numbers[0]), unit(numbers[1])]
+}
+
+fn hsv_from_numbers(numbers: &[&str], percent: [bool; 2]) -> Option<Hsv> {
+    let h: f32 = numbers[0].parse().ok()?;
+    let fraction = |number: &str, percent: bool| -> Option<f32> {
+        let value: f32 = number.trim_end_matches('%').parse().ok()?;
+        Some(if percent { value / 100.0 } else { value })
+    };
+    let s = fraction(numbers[1], percent[0])?;
+    let v = fraction(numbers[2], percent[1])?;
+    if h > 360.0 || s > 1.0 || v > 1.0 {
+        return None;
+    }
+    Some(Hsv::new(h, s, v))
+}
+
+impl ColorLiteral {
+    fn from_captures(text: &str, captures: &Captures, format: LiteralFormat) -> Option<Self> {
+        let whole = captures.get(0)?;
+        let parts: Vec<_> = captures
+            .iter()
+            .skip(1)
+            .map(|part| part.map(|part| part.start() - whole.start()..part.end() - whole.start()))
+            .collect::<Option<_>>()?;
+        let numbers: Vec<&str> = parts
+            .iter()
+            .map(|part| &whole.as_str()[part.clone()])
+            .collect();
+        let percent = match format {
+            LiteralFormat::Hsv => hsv_units(&numbers[1..]),
+            _ => [false; 2],
+        };
+        let color = match format {
+            LiteralFormat::Hex => {
+                let rgb = u32::from_str_radix(numbers[0], 16).ok()?;
+                hsv_from_rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+            }
+            LiteralFormat::Rgb => hsv_from_rgb(
+                numbers[0].parse().ok()?,
+                numbers[1].parse().ok()?,
+                numbers[2].parse().ok()?,
+            ),
+            LiteralFormat::Hsv => hsv_from_numbers(&numbers, percent)?,
+        };
+        Some(ColorLiteral {
+            span: whole.start()..whole.end(),
+            format,
+            color,
+            text: text[whole.start()..whole.end()].to_string(),
+            parts,
+            percent,
+        })
+    }
+
+    fn numbers(&self) -> Vec<&str> {
+        self.parts
+            .iter()
+            .map(|part| &self.text[part.clone()])
+            .collect()
+    }
+
+    /// The literal with `color` written in its notation: same prefix, case,
+    /// spacing and number precision.
+    pub fn rewrite(&self, color: &Hsv) -> String {
+        let (r, g, b) = rgb_from_hsv(color);
+        let numbers: Vec<String> = match self.format {
+            LiteralFormat::Hex => {
+                let hex = format!("{:02x}{:02x}{:02x}", r, g, b);
+                let digits = &self.text[self.parts[0].clone()];
+                if digits.chars().any(|c| c.is_ascii_uppercase()) {
+                    vec![hex.to_ascii_uppercase()]
+                } else {
+                    vec![hex]
+                }
+            }
+            LiteralFormat::Rgb => vec![r.to_string(), g.to_string(), b.to_string()],
+            LiteralFormat::Hsv => {
+                let original = self.numbers();
+                let hue = color.hue.into_positive_degrees();
+                let mut numbers = vec![format!("{:.*}", decimals(original[0]), hue)];
+                let values = [color.saturation, color.value];
+                for ((value, original), percent) in
+                    values.iter().zip(&original[1..]).zip(self.percent)
+                {
+                    let number = original.trim_end_matches('%');
+                    numbers.push(if percent {
+                        let suffix = if original.ends_with('%') { "%" } else { "" };
+                        format!("{:.*}{}", decimals(number), value * 100.0, suffix)
+                    } else {
+                        format!("{:.*}", decimals(number).max(2), value)
+                    });
+                }
+                numbers
+            }
+        };
+        let mut out = self.text.clone();
+        // From the end so the earlier spans stay valid
+        for (part, number) in self.parts.iter().zip(numbers).rev() {
+            out.replace_range(part.clone(), &number);
+        }
+        out
+    }
+
+    /// The literal rewritten with `color`, its numbers keep their units even
+    /// where the new text alone would read differently
+    pub fn rewritten(&self, color: &Hsv) -> Option<ColorLiteral> {
+        let text = self.rewrite(color);
+        let mut literal = literal_at(&text, Some(0))?;
+        if literal.format == LiteralFormat::Hsv {
+            literal.percent = self.percent;
+            literal.color = hsv_from_numbers(&literal.numbers(), self.percent)?;
+        }
+        Some(literal)
+    }
+}
+
+fn find_all(text: &str, regex: &Regex, format: LiteralFormat) -> Vec<ColorLiteral> {
+    regex
+        .captures_iter(text)
+        .filter_map(|captures| ColorLiteral::from_captures(text, &captures, format))
+        .collect()
+}
+
+/// Every unambiguous color literal of the text in order: `#rrggbb`,
+/// `0xrrggbb`, `rgb(r, g, b)` and `hsv(h, s, v)`.
+pub fn scan(text: &str) -> Vec<ColorLiteral> {
+    let mut literals = find_all(text, &HEX, LiteralFormat::Hex);
+    literals.extend(find_all(text, &RGB, LiteralFormat::Rgb));
+    literals.extend(find_all(text, &HSV, LiteralFormat::Hsv));
+    literals.sort_by_key(|literal| literal.span.start);
+    literals
+}
+
+/// Literal covering the byte offset of the line, the cursor can also be
+/// right after it. Bare hex colors and `(r, g, b)` tuples are accepted too.
+/// Without an offset, the first literal of the line.
+pub fn literal_at(line: &str, offset: Option<usize>) -> Option<ColorLiteral> {
+    let mut literals = scan(line);
+    literals.extend(find_all(line, &BARE_HEX, LiteralFormat::Hex));
+    literals.extend(find_all(line, &TUPLE, LiteralFormat::Rgb));
+    match offset {
+        Some(offset) => literals
+            .into_iter()
+            .find(|literal| literal.span.start <= offset && offset <= literal.span.end),
+        None => literals
+            .into_iter()
+            .min_by_key(|literal| literal.span.start),
+    }
+}
+
+#[test]
+fn test_color_literal_rewrite() {
+    let line = "  color: #FF5733; background: rgb( 12,34 , 56 ); border: hsv(120.0, 50%, 0.25);";
+    let literals = scan(line);
+    assert_eq!(literals.len(), 3);
+    assert_eq!(literals[0].text, "#FF5733");
+    assert_eq!(&line[literals[1].span.clone()], "rgb( 12,34 , 56 )");
+
+    let color = hsv_from_rgb(0x1d, 0x20, 0x21);
+    assert_eq!(literals[0].rewrite(&color), "#1D2021");
+    assert_eq!(literals[1].rewrite(&color), "rgb( 29,32 , 33 )");
+    assert_eq!(
+        literals[2].rewrite(&Hsv::new(200.0, 0.1, 0.5)),
+        "hsv(200.0, 10%, 0.50)"
+    );
+    // Rewriting with the same color gives the literal back
+    for literal in &literals {
+        assert_eq!(literal.rewrite(&literal.color), literal.text);
+    }
+
+    let at = literal_at(line, Some(35)).unwrap();
+    assert_eq!(at.format, LiteralFormat::Rgb);
+    assert_eq!(literal_at(line, Some(16)).unwrap().text, "#FF5733");
+    assert!(literal_at(line, Some(3)).is_none());
+    assert_eq!(
+        literal_at("let c = 0xff5733;", None).unwrap().text,
+        "0xff5733"
+    );
+    assert_eq!(literal_at("fg = ff5733", None).unwrap().text, "ff5733");
+    assert!(scan("fg = ff5733, (1, 2, 3), rgb(300, 0, 0)").is_empty());
+}
+
+#[test]
+fn test_hsv_literal_units() {
+    let percent = literal_at("hsv(120, 1, 50)", None).unwrap();
+    assert_eq!(percent.color, Hsv::new(120.0, 0.01, 0.5));
+    let fraction = literal_at("hsv(0, 0, 1)", None).unwrap();
+    assert_eq!(fraction.color, Hsv::new(0.0, 0.0, 1.0));
+    let mixed = literal_at("hsv(120, 50%, 1)", None).unwrap();
+    assert_eq!(mixed.color.value, 0.01);
+
+    // Rewritten to numbers at most 1, still percentages
+    let dim = Hsv::new(120.0, 0.01, 0.01);
+    let rewritten = percent.rewritten(&dim).unwrap();
+    assert_eq!(rewritten.text, "hsv(120, 1, 1)");
+    assert_eq!(rewritten.color, dim);
+    assert_eq!(
+        rewritten.rewrite(&Hsv::new(120.0, 0.5, 1.0)),
+        "hsv(120, 50, 100)"
+    );
+}
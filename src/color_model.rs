@@ -0,0 +1,113 @@
+use palette::{FromColor, Hsv, Srgb};
+
+// The selected color is kept as f32 HSV and only rounded for display. Edits
+// made in another notation go through these helpers so they neither quantize
+// the other channels nor lose the hue of grays.
+
+/// Saturation or value under this has no visible effect after rounding, the
+/// hue (and the saturation for black) is undefined
+const ACHROMATIC_EPSILON: f32 = 1e-4;
+
+/// RGB channels from 0 to 1, without rounding
+pub fn rgb_channels(color: &Hsv) -> [f32; 3] {
+    Srgb::from_color(*color).into()
+}
+
+/// `color` with the hue of `previous` if it has none, and with its saturation
+/// too if it is black. Conversions from RGB, CMYK or CIE values lose them, so
+/// typing R on a gray would otherwise reset the hue strip.
+pub fn keep_hue(mut color: Hsv, previous: &Hsv) -> Hsv {
+    if color.value < ACHROMATIC_EPSILON {
+        color.value = 0.0;
+        color.hue = previous.hue;
+        color.saturation = previous.saturation;
+    } else if color.saturation < ACHROMATIC_EPSILON {
+        color.saturation = 0.0;
+        color.hue = previous.hue;
+    }
+    color
+}
+
+/// Sets one RGB channel, from 0 to 1, leaving the two others untouched
+pub fn with_rgb_channel(color: &Hsv, channel: usize, value: f32) -> Hsv {
+    let mut rgb = rgb_channels(color);
+    rgb[channel] = value.clamp(0.0, 1.0);
+    keep_hue(Hsv::from_color(Srgb::from(rgb)), color)
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_rgb_edits_do_not_drift(
+        hue in 0.0f32..360.0,
+        saturation in 0.0f32..=1.0,
+        value in 0.0f32..=1.0,
+        edits in proptest::collection::vec((0usize..3, 0u8..=255), 1..50),
+    ) {
+        use crate::utils::rgb_from_hsv;
+
+        let mut color = Hsv::new(hue, saturation, value);
+        for (channel, typed) in edits {
+            let before = rgb_channels(&color);
+            color = with_rgb_channel(&color, channel, typed as f32 / 255.0);
+            let after = rgb_channels(&color);
+            // The edited channel reads back as typed, the others are untouched
+            let (r, g, b) = rgb_from_hsv(&color);
+            proptest::prop_assert_eq!([r, g, b][channel], typed);
+            for other in (0..3).filter(|other| *other != channel) {
+                proptest::prop_assert!((after[other] - before[other]).abs() < 1e-5);
+            }
+            // Typing the same value again changes nothing
+            let again = rgb_channels(&with_rgb_channel(&color, channel, typed as f32 / 255.0));
+            for (a, b) in again.iter().zip(after) {
+                proptest::prop_assert!((a - b).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_grays_keep_their_hue(
+        hue in 0.0f32..360.0,
+        saturation in 0.0f32..=1.0,
+        gray in 0u8..=255,
+        channel in 0usize..3,
+    ) {
+        // A gray or a black remembering the hue and saturation it came from
+        let value = gray as f32 / 255.0;
+        let color = Hsv::new(hue, if gray == 0 { saturation } else { 0.0 }, value);
+        let edited = with_rgb_channel(&color, channel, value);
+        proptest::prop_assert_eq!(edited.hue, color.hue);
+        proptest::prop_assert_eq!(edited.saturation, color.saturation);
+        proptest::prop_assert!((edited.value - value).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_conversions_round_trip(
+        hue in 0.0f32..360.0,
+        saturation in 0.0f32..=1.0,
+        value in 0.0f32..=1.0,
+        space in 0usize..3,
+    ) {
+        use crate::color_space::{ColorSettings, WorkingSpace};
+
+        let space = [WorkingSpace::Srgb, WorkingSpace::DisplayP3, WorkingSpace::Rec2020][space];
+        let settings = ColorSettings { space, ..Default::default() };
+        // An sRGB color fits in every working space
+        let color = Hsv::new(hue, saturation, value);
+        let working = settings.from_srgb(&color);
+        let (l, a, b) = settings.lab(&working);
+        let results = [
+            keep_hue(settings.srgb(&working), &color),
+            keep_hue(settings.srgb(&settings.from_lab(l, a, b)), &color),
+        ];
+        for result in results {
+            let (expected, actual) = (rgb_channels(&color), rgb_channels(&result));
+            for (e, a) in expected.iter().zip(actual) {
+                proptest::prop_assert!((e - a).abs() < 1e-3);
+            }
+            if saturation < ACHROMATIC_EPSILON || value < ACHROMATIC_EPSILON {
+                proptest::prop_assert_eq!(result.hue, color.hue);
+            }
+        }
+    }
+}
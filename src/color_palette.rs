@@ -0,0 +1,45 @@
+use crate::utils::rgb_from_hsv;
+use palette::Hsv;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Swatch {
+    pub label: String,
+    pub color: Hsv,
+}
+
+/// Named list of labeled colors, loaded in the palette strip
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColorPalette {
+    pub name: String,
+    pub swatches: Vec<Swatch>,
+}
+
+impl ColorPalette {
+    pub fn new(name: impl Into<String>) -> Self {
+        ColorPalette {
+            name: name.into(),
+            swatches: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, label: impl Into<String>, color: Hsv) {
+        self.swatches.push(Swatch {
+            label: label.into(),
+            color,
+        });
+    }
+
+    pub fn colors(&self) -> Vec<Hsv> {
+        self.swatches.iter().map(|swatch| swatch.color).collect()
+    }
+
+    /// GIMP palette format, also read by Inkscape and Krita
+    pub fn to_gpl(&self) -> String {
+        let mut out = format!("GIMP Palette\nName: {}\nColumns: 8\n#\n", self.name);
+        for swatch in &self.swatches {
+            let (r, g, b) = rgb_from_hsv(&swatch.color);
+            out.push_str(&format!("{:3} {:3} {:3}\t{}\n", r, g, b, swatch.label));
+        }
+        out
+    }
+}
@@ -0,0 +1,390 @@
+use palette::white_point::D65;
+use palette::{FromColor, Hsv, Oklab, Srgb, Xyz, convert::FromColorUnclamped};
+
+use crate::cie::*;
+
+/// RGB space the picker, the RGB/HSV inputs and `color()` work in. Hex, `rgb()`
+/// and `hsv()` are always sRGB, as in CSS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorkingSpace {
+    #[default]
+    Srgb,
+    DisplayP3,
+    Rec2020,
+}
+
+/// How colors outside of the target space are brought into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GamutMapping {
+    /// Clamps each channel, which can shift the hue
+    #[default]
+    Clip,
+    /// Reduces the OKLCh chroma until the color fits, as in CSS Color 4
+    Css,
+}
+
+/// Everything needed to convert the selected color to another notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ColorSettings {
+    /// Reference white of the Lab, LCh and XYZ values
+    pub white_point: WhitePoint,
+    pub space: WorkingSpace,
+    pub gamut_mapping: GamutMapping,
+    /// Decimals of the copied values, one more for those from 0 to 1. `None`
+    /// keeps the fixed decimals of each format.
+    pub precision: Option<usize>,
+}
+
+/// More does not fit in the copy format selector
+pub const MAX_PRECISION: usize = 3;
+
+type Matrix = [[f32; 3]; 3];
+
+// Linear RGB to XYZ D65 and back, from the CSS Color 4 sample code
+const SRGB_TO_XYZ: Matrix = [
+    [0.412_390_8, 0.357_584_33, 0.180_480_8],
+    [0.212_639, 0.715_168_7, 0.072_192_32],
+    [0.019_330_818, 0.119_194_78, 0.950_532_2],
+];
+const XYZ_TO_SRGB: Matrix = [
+    [3.240_97, -1.537_383_2, -0.498_610_76],
+    [-0.969_243_65, 1.875_967_5, 0.041_555_06],
+    [0.055_630_08, -0.203_976_96, 1.056_971_5],
+];
+const DISPLAY_P3_TO_XYZ: Matrix = [
+    [0.486_570_95, 0.265_667_7, 0.198_217_29],
+    [0.228_974_56, 0.691_738_5, 0.079_286_91],
+    [0.0, 0.045_113_38, 1.043_944_4],
+];
+const XYZ_TO_DISPLAY_P3: Matrix = [
+    [2.493_497, -0.931_383_6, -0.402_710_8],
+    [-0.829_489, 1.762_664_1, 0.023_624_686],
+    [0.035_845_83, -0.076_172_39, 0.956_884_5],
+];
+const REC2020_TO_XYZ: Matrix = [
+    [0.636_958, 0.144_616_9, 0.168_880_98],
+    [0.262_700_2, 0.677_998_1, 0.059_301_716],
+    [0.0, 0.028_072_693, 1.060_985_1],
+];
+const XYZ_TO_REC2020: Matrix = [
+    [1.716_651_2, -0.355_670_8, -0.253_366_3],
+    [-0.666_684_4, 1.616_481_2, 0.015_768_546],
+    [0.017_639_857, -0.042_770_613, 0.942_103_1],
+];
+
+const REC2020_ALPHA: f32 = 1.099_296_8;
+const REC2020_BETA: f32 = 0.018_053_97;
+
+/// Allowed error when checking gamut bounds
+const GAMUT_EPSILON: f32 = 0.000_05;
+
+fn multiply(matrix: &Matrix, [a, b, c]: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * a + row[1] * b + row[2] * c)
+}
+
+impl WorkingSpace {
+    pub const NAMES: &str = "srgb, display-p3, rec2020";
+
+    pub fn parse(name: &str) -> Option<WorkingSpace> {
+        match name.to_ascii_lowercase().as_str() {
+            "srgb" => Some(WorkingSpace::Srgb),
+            "display-p3" | "p3" => Some(WorkingSpace::DisplayP3),
+            "rec2020" | "rec.2020" => Some(WorkingSpace::Rec2020),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WorkingSpace::Srgb => "sRGB",
+            WorkingSpace::DisplayP3 => "P3",
+            WorkingSpace::Rec2020 => "Rec.2020",
+        }
+    }
+
+    /// Predefined color space of CSS `color()`
+    pub fn css_name(&self) -> &'static str {
+        match self {
+            WorkingSpace::Srgb => "srgb",
+            WorkingSpace::DisplayP3 => "display-p3",
+            WorkingSpace::Rec2020 => "rec2020",
+        }
+    }
+
+    /// Cycles through the spaces, for `:space` without argument
+    pub fn next(&self) -> WorkingSpace {
+        match self {
+            WorkingSpace::Srgb => WorkingSpace::DisplayP3,
+            WorkingSpace::DisplayP3 => WorkingSpace::Rec2020,
+            WorkingSpace::Rec2020 => WorkingSpace::Srgb,
+        }
+    }
+
+    fn linearize(&self, channel: f32) -> f32 {
+        let abs = channel.abs();
+        let linear = match self {
+            // Display P3 uses the sRGB transfer function
+            WorkingSpace::Srgb | WorkingSpace::DisplayP3 if abs <= 0.04045 => abs / 12.92,
+            WorkingSpace::Srgb | WorkingSpace::DisplayP3 => ((abs + 0.055) / 1.055).powf(2.4),
+            WorkingSpace::Rec2020 if abs < REC2020_BETA * 4.5 => abs / 4.5,
+            WorkingSpace::Rec2020 => ((abs + REC2020_ALPHA - 1.0) / REC2020_ALPHA).powf(1.0 / 0.45),
+        };
+        linear.copysign(channel)
+    }
+
+    fn encode(&self, channel: f32) -> f32 {
+        let abs = channel.abs();
+        let encoded = match self {
+            WorkingSpace::Srgb | WorkingSpace::DisplayP3 if abs <= 0.003_130_8 => abs * 12.92,
+            WorkingSpace::Srgb | WorkingSpace::DisplayP3 => 1.055 * abs.powf(1.0 / 2.4) - 0.055,
+            WorkingSpace::Rec2020 if abs < REC2020_BETA => abs * 4.5,
+            WorkingSpace::Rec2020 => REC2020_ALPHA * abs.powf(0.45) - (REC2020_ALPHA - 1.0),
+        };
+        encoded.copysign(channel)
+    }
+
+    fn matrices(&self) -> (&'static Matrix, &'static Matrix) {
+        match self {
+            WorkingSpace::Srgb => (&SRGB_TO_XYZ, &XYZ_TO_SRGB),
+            WorkingSpace::DisplayP3 => (&DISPLAY_P3_TO_XYZ, &XYZ_TO_DISPLAY_P3),
+            WorkingSpace::Rec2020 => (&REC2020_TO_XYZ, &XYZ_TO_REC2020),
+        }
+    }
+
+    /// Reads `color` as coordinates in this space
+    pub fn to_xyz(&self, color: &Hsv) -> Xyz<D65> {
+        let rgb: [f32; 3] = Srgb::from_color(*color).into();
+        let [x, y, z] = multiply(self.matrices().0, rgb.map(|c| self.linearize(c)));
+        Xyz::new(x, y, z)
+    }
+
+    /// Gamma encoded coordinates, out of 0..=1 for colors outside of the space
+    fn rgb_from_xyz(&self, xyz: Xyz<D65>) -> [f32; 3] {
+        let linear = multiply(self.matrices().1, xyz.into_components().into());
+        linear.map(|c| self.encode(c))
+    }
+
+    pub fn contains(&self, xyz: Xyz<D65>) -> bool {
+        self.rgb_from_xyz(xyz)
+            .iter()
+            .all(|c| (-GAMUT_EPSILON..=1.0 + GAMUT_EPSILON).contains(c))
+    }
+
+    fn clip(&self, xyz: Xyz<D65>) -> [f32; 3] {
+        self.rgb_from_xyz(xyz).map(|c| c.clamp(0.0, 1.0))
+    }
+
+    /// Converts to coordinates in this space, mapping colors outside of it
+    pub fn from_xyz(&self, xyz: Xyz<D65>, mapping: GamutMapping) -> Hsv {
+        let [r, g, b] = match mapping {
+            GamutMapping::Clip => self.clip(xyz),
+            GamutMapping::Css => self.css_gamut_map(xyz),
+        };
+        Hsv::from_color(Srgb::new(r, g, b))
+    }
+
+    /// CSS Color 4 gamut mapping: binary search on the OKLCh chroma for the
+    /// least saturated color whose clipped version is within a just noticeable
+    /// difference of it.
+    fn css_gamut_map(&self, xyz: Xyz<D65>) -> [f32; 3] {
+        const JND: f32 = 0.02;
+        const EPSILON: f32 = 0.0001;
+
+        let origin = Oklab::from_color_unclamped(xyz);
+        if origin.l >= 1.0 {
+            return [1.0; 3];
+        }
+        if origin.l <= 0.0 {
+            return [0.0; 3];
+        }
+        if self.contains(xyz) {
+            return self.clip(xyz);
+        }
+        let chroma = origin.a.hypot(origin.b);
+        let with_chroma = |c: f32| {
+            let scale = c / chroma;
+            Oklab::new(origin.l, origin.a * scale, origin.b * scale)
+        };
+        let clipped_of = |oklab: Oklab| {
+            let clipped = self.clip(Xyz::from_color_unclamped(oklab));
+            let [r, g, b] = clipped.map(|c| self.linearize(c));
+            let [x, y, z] = multiply(self.matrices().0, [r, g, b]);
+            let clipped_oklab = Oklab::from_color_unclamped(Xyz::<D65>::new(x, y, z));
+            let delta_e = (oklab.l - clipped_oklab.l)
+                .hypot(oklab.a - clipped_oklab.a)
+                .hypot(oklab.b - clipped_oklab.b);
+            (clipped, delta_e)
+        };
+
+        let (mut clipped, delta_e) = clipped_of(origin);
+        if delta_e < JND {
+            return clipped;
+        }
+        let (mut min, mut max) = (0.0, chroma);
+        let mut min_in_gamut = true;
+        while max - min > EPSILON {
+            let c = (min + max) / 2.0;
+            let current = with_chroma(c);
+            if min_in_gamut && self.contains(Xyz::from_color_unclamped(current)) {
+                min = c;
+                continue;
+            }
+            let delta_e;
+            (clipped, delta_e) = clipped_of(current);
+            if delta_e < JND {
+                if JND - delta_e < EPSILON {
+                    break;
+                }
+                min_in_gamut = false;
+                min = c;
+            } else {
+                max = c;
+            }
+        }
+        clipped
+    }
+}
+
+impl GamutMapping {
+    pub const NAMES: &str = "clip, css";
+
+    pub fn parse(name: &str) -> Option<GamutMapping> {
+        match name.to_ascii_lowercase().as_str() {
+            "clip" => Some(GamutMapping::Clip),
+            "css" => Some(GamutMapping::Css),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GamutMapping::Clip => "clip",
+            GamutMapping::Css => "CSS Color 4",
+        }
+    }
+
+    pub fn toggled(&self) -> GamutMapping {
+        match self {
+            GamutMapping::Clip => GamutMapping::Css,
+            GamutMapping::Css => GamutMapping::Clip,
+        }
+    }
+}
+
+/// Conversions of a color in the working space. Lab, LCh and XYZ are exact,
+/// colors going to a narrower RGB space are gamut mapped.
+impl ColorSettings {
+    /// The color as used outside of the picker: hex, `rgb()`, `hsv()`, CMYK,
+    /// history, palettes and the terminal
+    pub fn srgb(&self, color: &Hsv) -> Hsv {
+        self.convert(color, self.space, WorkingSpace::Srgb)
+    }
+
+    /// Brings an sRGB color into the working space
+    pub fn from_srgb(&self, color: &Hsv) -> Hsv {
+        self.convert(color, WorkingSpace::Srgb, self.space)
+    }
+
+    pub fn convert(&self, color: &Hsv, from: WorkingSpace, to: WorkingSpace) -> Hsv {
+        if from == to {
+            return *color;
+        }
+        to.from_xyz(from.to_xyz(color), self.gamut_mapping)
+    }
+
+    pub fn in_srgb(&self, color: &Hsv) -> bool {
+        self.space == WorkingSpace::Srgb || WorkingSpace::Srgb.contains(self.space.to_xyz(color))
+    }
+
+    pub fn from_xyz(&self, xyz: Xyz<D65>) -> Hsv {
+        self.space.from_xyz(xyz, self.gamut_mapping)
+    }
+
+    /// XYZ with Y from 0 to 1
+    pub fn xyz(&self, color: &Hsv) -> (f32, f32, f32) {
+        xyz_components(self.space.to_xyz(color), self.white_point)
+    }
+
+    pub fn from_xyz_components(&self, x: f32, y: f32, z: f32) -> Hsv {
+        self.from_xyz(xyz_from_components(x, y, z, self.white_point))
+    }
+
+    /// CIELAB with L from 0 to 100
+    pub fn lab(&self, color: &Hsv) -> (f32, f32, f32) {
+        lab_from_xyz(self.space.to_xyz(color), self.white_point)
+    }
+
+    pub fn from_lab(&self, l: f32, a: f32, b: f32) -> Hsv {
+        self.from_xyz(xyz_from_lab(l, a, b, self.white_point))
+    }
+
+    /// Cylindrical CIELAB, the hue is in degrees from 0 to 360
+    pub fn lch(&self, color: &Hsv) -> (f32, f32, f32) {
+        lch_from_xyz(self.space.to_xyz(color), self.white_point)
+    }
+
+    pub fn from_lch(&self, l: f32, chroma: f32, hue: f32) -> Hsv {
+        self.from_xyz(xyz_from_lch(l, chroma, hue, self.white_point))
+    }
+
+    /// Same settings with Lab and LCh relative to D50, as CSS requires
+    pub fn css(&self) -> ColorSettings {
+        ColorSettings {
+            white_point: WhitePoint::D50,
+            ..*self
+        }
+    }
+}
+
+/// Used where no settings apply, like files and IPC requests
+pub fn srgb_from_xyz(xyz: Xyz<D65>) -> Hsv {
+    WorkingSpace::Srgb.from_xyz(xyz, GamutMapping::Clip)
+}
+
+#[test]
+fn test_working_space_conversions() {
+    use crate::utils::{hsv_from_rgb, rgb_from_hsv};
+
+    let srgb = ColorSettings::default();
+    let p3 = ColorSettings {
+        space: WorkingSpace::DisplayP3,
+        ..Default::default()
+    };
+    let rec2020 = ColorSettings {
+        space: WorkingSpace::Rec2020,
+        ..Default::default()
+    };
+    let red = hsv_from_rgb(255, 0, 0);
+    // sRGB red in P3 and Rec.2020, from the CSS Color 4 sample code
+    let in_p3: [f32; 3] = Srgb::from_color(p3.from_srgb(&red)).into();
+    assert!((in_p3[0] - 0.9175).abs() < 0.001 && (in_p3[1] - 0.2003).abs() < 0.001);
+    let in_rec2020: [f32; 3] = Srgb::from_color(rec2020.from_srgb(&red)).into();
+    assert!((in_rec2020[0] - 0.7920).abs() < 0.001 && (in_rec2020[1] - 0.2310).abs() < 0.001);
+
+    for settings in [srgb, p3, rec2020] {
+        for rgb in [(255, 87, 51), (0, 0, 255), (128, 128, 128), (255, 255, 255)] {
+            let color = hsv_from_rgb(rgb.0, rgb.1, rgb.2);
+            let working = settings.from_srgb(&color);
+            assert!(settings.in_srgb(&working));
+            assert_eq!(rgb_from_hsv(&settings.srgb(&working)), rgb);
+        }
+    }
+
+    // P3 red is outside of sRGB, clipping keeps the channels saturated and CSS
+    // mapping reduces the chroma without changing the lightness much
+    let p3_red = hsv_from_rgb(255, 0, 0);
+    assert!(!p3.in_srgb(&p3_red));
+    assert_eq!(rgb_from_hsv(&p3.srgb(&p3_red)), (255, 0, 0));
+    let css = ColorSettings {
+        gamut_mapping: GamutMapping::Css,
+        ..p3
+    };
+    let mapped = css.srgb(&p3_red);
+    assert!(WorkingSpace::Srgb.contains(WorkingSpace::Srgb.to_xyz(&mapped)));
+    let lightness = |xyz: Xyz<D65>| Oklab::from_color_unclamped(xyz).l;
+    let difference =
+        lightness(WorkingSpace::Srgb.to_xyz(&mapped)) - lightness(p3.space.to_xyz(&p3_red));
+    assert!(difference.abs() < 0.02);
+    let (r, g, b) = rgb_from_hsv(&mapped);
+    assert!(r == 255 && g > 0 && b > 0);
+}
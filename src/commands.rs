@@ -0,0 +1,232 @@
+use crate::cie::WhitePoint;
+use crate::color_space::{GamutMapping, MAX_PRECISION, WorkingSpace};
+use crate::constants::{INPUTS_MIN_STEP, PALETTE_CAPACITY};
+use crate::scheme::SchemeFormat;
+use crate::terminal_palette::Slot;
+use std::path::PathBuf;
+
+/// Commands entered in the `:` prompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Open(PathBuf),
+    /// Dominant colors of the opened image, with an optional count
+    Extract(Option<usize>),
+    /// Load a theme file as an editable palette
+    Import(PathBuf),
+    /// Write the imported palette back, optionally to another file
+    Write(Option<PathBuf>),
+    /// Palette reported by the terminal at startup
+    Terminal,
+    /// Edit a terminal palette slot live, `None` stops editing
+    EditSlot(Option<Slot>),
+    /// Write the terminal palette as a color scheme
+    Export(SchemeFormat, PathBuf),
+    /// Reference white of the CIE values, `None` toggles it
+    WhitePoint(Option<WhitePoint>),
+    /// RGB space of the picker and inputs, `None` cycles through them
+    Space(Option<WorkingSpace>),
+    /// How colors are brought into sRGB, `None` toggles it
+    GamutMap(Option<GamutMapping>),
+    /// Decimals of the copied values
+    /// `None` goes back to the fixed decimals of each format
+    Precision(Option<usize>),
+    /// Nudges of the input fields, the big step is optional
+    Step(f32, Option<f32>),
+    /// Magnification of the pickers around the color, `None` toggles it
+    Zoom(Option<f32>),
+    Quit,
+}
+
+pub fn parse_command(line: &str) -> Result<Action, String> {
+    let (name, argument) = match line.trim().split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (line.trim(), ""),
+    };
+    match name {
+        "o" | "open" => {
+            if argument.is_empty() {
+                return Err("Usage: :open <path>".to_string());
+            }
+            Ok(Action::Open(expand_home(argument)))
+        }
+        "extract" => {
+            if argument.is_empty() {
+                return Ok(Action::Extract(None));
+            }
+            let count: usize = argument
+                .parse()
+                .map_err(|_| format!("Invalid color count: {}", argument))?;
+            if count > PALETTE_CAPACITY {
+                return Err(format!(
+                    "At most {} colors can be extracted",
+                    PALETTE_CAPACITY
+                ));
+            }
+            Ok(Action::Extract(Some(count)))
+        }
+        "import" => {
+            if argument.is_empty() {
+                return Err("Usage: :import <path>".to_string());
+            }
+            Ok(Action::Import(expand_home(argument)))
+        }
+        "w" | "write" => Ok(Action::Write(
+            (!argument.is_empty()).then(|| expand_home(argument)),
+        )),
+        "terminal" => Ok(Action::Terminal),
+        "slot" => match argument {
+            "" | "off" => Ok(Action::EditSlot(None)),
+            _ => Slot::parse(argument)
+                .map(|slot| Action::EditSlot(Some(slot)))
+                .ok_or(format!(
+                    "Invalid slot: {}, use 0-15, fg, bg or cursor",
+                    argument
+                )),
+        },
+        "export" => {
+            if argument.is_empty() {
+                return Err("Usage: :export [format] <path>".to_string());
+            }
+            if let Some((format, path)) = argument.split_once(char::is_whitespace)
+                && let Some(format) = SchemeFormat::parse(format)
+            {
+                return Ok(Action::Export(format, expand_home(path.trim())));
+            }
+            let path = expand_home(argument);
+            let format = SchemeFormat::from_path(&path).ok_or(format!(
+                "Unknown scheme format, use :export <format> <path> with {}",
+                SchemeFormat::NAMES
+            ))?;
+            Ok(Action::Export(format, path))
+        }
+        "whitepoint" => match argument {
+            "" => Ok(Action::WhitePoint(None)),
+            _ => WhitePoint::parse(argument)
+                .map(|white_point| Action::WhitePoint(Some(white_point)))
+                .ok_or(format!(
+                    "Invalid white point: {}, use {}",
+                    argument,
+                    WhitePoint::NAMES
+                )),
+        },
+        "space" => match argument {
+            "" => Ok(Action::Space(None)),
+            _ => WorkingSpace::parse(argument)
+                .map(|space| Action::Space(Some(space)))
+                .ok_or(format!(
+                    "Invalid color space: {}, use {}",
+                    argument,
+                    WorkingSpace::NAMES
+                )),
+        },
+        "gamutmap" => match argument {
+            "" => Ok(Action::GamutMap(None)),
+            _ => GamutMapping::parse(argument)
+                .map(|mapping| Action::GamutMap(Some(mapping)))
+                .ok_or(format!(
+                    "Invalid gamut mapping: {}, use {}",
+                    argument,
+                    GamutMapping::NAMES
+                )),
+        },
+        "precision" if argument.is_empty() => Ok(Action::Precision(None)),
+        "precision" => match argument.parse() {
+            Ok(precision) if precision <= MAX_PRECISION => Ok(Action::Precision(Some(precision))),
+            _ => Err(format!("Usage: :precision [0-{}]", MAX_PRECISION)),
+        },
+        "step" => {
+            let mut steps = argument.split_whitespace().map(parse_step);
+            match (steps.next(), steps.next(), steps.next()) {
+                (Some(Some(step)), None, None) => Ok(Action::Step(step, None)),
+                (Some(Some(step)), Some(Some(big_step)), None) => {
+                    Ok(Action::Step(step, Some(big_step)))
+                }
+                _ => Err(format!(
+                    "Usage: :step <step> [big step], from {}",
+                    INPUTS_MIN_STEP
+                )),
+            }
+        }
+        "zoom" if argument.is_empty() => Ok(Action::Zoom(None)),
+        "zoom" => argument
+            .parse()
+            .ok()
+            .filter(|factor: &f32| *factor >= 1.0 && factor.is_finite())
+            .map(|factor| Action::Zoom(Some(factor)))
+            .ok_or("Usage: :zoom [factor, 1 to zoom out]".to_string()),
+        "q" | "quit" => Ok(Action::Quit),
+        "" => Err("No command".to_string()),
+        _ => Err(format!("Unknown command: {}", name)),
+    }
+}
+
+/// A number from `INPUTS_MIN_STEP`, the inputs round to its decimals
+pub fn parse_step(text: &str) -> Option<f32> {
+    text.parse()
+        .ok()
+        .filter(|step: &f32| *step >= INPUTS_MIN_STEP && step.is_finite())
+}
+
+/// Replaces a leading `~/` with the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(home) = std::env::var_os("HOME")
+    {
+        return PathBuf::from(home).join(rest);
+    }
+    PathBuf::from(path)
+}
+
+#[test]
+fn test_parse_command() {
+    assert_eq!(
+        parse_command("open shots/mockup 2.png"),
+        Ok(Action::Open(PathBuf::from("shots/mockup 2.png")))
+    );
+    assert_eq!(parse_command("  q "), Ok(Action::Quit));
+    assert!(parse_command("open").is_err());
+    assert_eq!(parse_command("extract 5"), Ok(Action::Extract(Some(5))));
+    assert!(parse_command("extract 100000").is_err());
+    assert_eq!(
+        parse_command("slot bg"),
+        Ok(Action::EditSlot(Some(Slot::Background)))
+    );
+    assert_eq!(parse_command("slot off"), Ok(Action::EditSlot(None)));
+    assert_eq!(
+        parse_command("export kitty theme"),
+        Ok(Action::Export(SchemeFormat::Kitty, PathBuf::from("theme")))
+    );
+    assert_eq!(
+        parse_command("export my theme.ini"),
+        Ok(Action::Export(
+            SchemeFormat::Foot,
+            PathBuf::from("my theme.ini")
+        ))
+    );
+    assert!(parse_command("export theme").is_err());
+    assert_eq!(parse_command("w"), Ok(Action::Write(None)));
+    assert_eq!(
+        parse_command("whitepoint D65"),
+        Ok(Action::WhitePoint(Some(WhitePoint::D65)))
+    );
+    assert_eq!(
+        parse_command("space display-p3"),
+        Ok(Action::Space(Some(WorkingSpace::DisplayP3)))
+    );
+    assert_eq!(parse_command("gamutmap"), Ok(Action::GamutMap(None)));
+    assert!(parse_command("space adobe-rgb").is_err());
+    assert_eq!(parse_command("precision 0"), Ok(Action::Precision(Some(0))));
+    assert_eq!(parse_command("precision"), Ok(Action::Precision(None)));
+    assert!(parse_command("precision 9").is_err());
+    assert_eq!(
+        parse_command("step 0.5 5"),
+        Ok(Action::Step(0.5, Some(5.0)))
+    );
+    assert!(parse_command("step -1").is_err());
+    assert_eq!(parse_command("step 0.01"), Ok(Action::Step(0.01, None)));
+    assert!(parse_command("step 0.001").is_err());
+    assert_eq!(parse_command("zoom"), Ok(Action::Zoom(None)));
+    assert_eq!(parse_command("zoom 16"), Ok(Action::Zoom(Some(16.0))));
+    assert!(parse_command("zoom 0.5").is_err());
+    assert!(parse_command("frobnicate").is_err());
+}
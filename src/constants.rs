@@ -1,4 +1,5 @@
 use crate::types::Vec2;
+use std::time::Duration;
 
 // This file contains all compile-time constants used in the app
 
@@ -23,6 +24,18 @@ pub const TEXT_COLOR: CustomRgb = CustomRgb {
     b: 0xff,
 };
 
+pub const SUCCESS_COLOR: CustomRgb = CustomRgb {
+    r: 0x8a,
+    g: 0xe2,
+    b: 0x34,
+};
+
+pub const ERROR_COLOR: CustomRgb = CustomRgb {
+    r: 0xef,
+    g: 0x45,
+    b: 0x45,
+};
+
 pub const FADE_VALUE_FACTOR: f32 = 0.4;
 
 pub const FADED_TEXT_COLOR: CustomRgb = CustomRgb {
@@ -32,41 +45,107 @@ pub const FADED_TEXT_COLOR: CustomRgb = CustomRgb {
 };
 
 pub const VALUE_DISPLAY_REL_POS: Vec2 = Vec2 { x: 0, y: 0 };
+/// The second line has the CIE values
+pub const VALUE_DISPLAY_HEIGHT: u32 = 2;
+
+pub const ANSI_PREVIEW_REL_POS: Vec2 = Vec2 {
+    x: 0,
+    y: VALUE_DISPLAY_HEIGHT,
+};
 
-pub const SV_PICKER_REL_POS: Vec2 = Vec2 { x: 0, y: 2 };
+pub const SV_PICKER_REL_POS: Vec2 = Vec2 {
+    x: 0,
+    y: ANSI_PREVIEW_REL_POS.y + 1,
+};
 pub const SV_PICKER_WIDTH: u32 = 60;
 pub const SV_PICKER_HEIGHT: u32 = SV_PICKER_WIDTH / 2;
 
 pub const HUE_PICKER_REL_POS: Vec2 = Vec2 {
     x: SV_PICKER_WIDTH + 3,
-    y: 2,
+    y: SV_PICKER_REL_POS.y,
 };
 pub const HUE_PICKER_HEIGHT: u32 = SV_PICKER_HEIGHT;
 pub const HUE_PICKER_WIDTH: u32 = 6;
 
+/// Degrees of hue added by a wheel notch over the hue strip
+pub const WHEEL_HUE_STEP: f32 = 1.0;
+/// Saturation or value added by a wheel notch over the SV square
+pub const WHEEL_SV_STEP: f32 = 0.01;
+/// Wheel steps are divided by this with Ctrl
+pub const WHEEL_FINE_DIVISOR: f32 = 10.0;
+/// Wheel steps further apart start a new burst
+pub const WHEEL_BURST_GAP: Duration = Duration::from_millis(500);
+/// Magnification of the pickers toggled with `z`
+pub const DEFAULT_ZOOM: f32 = 8.0;
+
 pub const INPUTS_REL_POS: Vec2 = Vec2 {
     x: HUE_PICKER_REL_POS.x + HUE_PICKER_WIDTH + 3,
-    y: 2,
+    y: SV_PICKER_REL_POS.y,
 };
 pub const INPUTS_CB_HEIGHT: u32 = 4;
 pub const INPUTS_CB_WIDTH: u16 = 16;
+/// Offsets of the columns of inputs: RGB and HSV, CMYK and LCh, then Lab and
+/// XYZ
+pub const INPUTS_COLUMNS: [u32; 3] = [0, 8, 17];
+/// Ends after the widest field of the last column
+pub const INPUTS_WIDTH: u16 = 26;
 pub const INPUTS_HEIGHT: u32 = INPUTS_CB_HEIGHT + 9;
+pub const INPUTS_DEFAULT_STEP: f32 = 1.0;
+pub const INPUTS_DEFAULT_BIG_STEP: f32 = 10.0;
+/// Decimals of the nudged values, smaller steps would not show
+pub const INPUTS_DECIMALS: usize = 2;
+pub const INPUTS_MIN_STEP: f32 = 0.01;
 
-pub const COPY_FORMAT_SELECTOR_SPACING: u16 = 6;
-pub const COPY_FORMAT_SELECTOR_WIDTH: u16 = COPY_FORMAT_SELECTOR_SPACING + 18;
-pub const COPY_FORMAT_SELECTOR_HEIGHT: u16 = 4;
+pub const COPY_FORMAT_SELECTOR_SPACING: u16 = 7;
+// Longer texts are cut with an ellipsis, the copy is complete
+pub const COPY_FORMAT_SELECTOR_WIDTH: u16 = INPUTS_WIDTH;
+pub const COPY_FORMAT_SELECTOR_HEIGHT: u16 = 10;
 pub const COPY_FORMAT_SELECTOR_RES_POS: Vec2 = Vec2 {
     x: INPUTS_REL_POS.x,
-    y: INPUTS_REL_POS.y + INPUTS_HEIGHT + 2,
+    y: INPUTS_REL_POS.y + INPUTS_HEIGHT + 1,
+};
+
+pub const HISTORY_LEN: usize = 10;
+pub const HISTORY_SWATCH_WIDTH: u16 = 2;
+pub const HISTORY_STRIP_REL_POS: Vec2 = Vec2 {
+    x: INPUTS_REL_POS.x,
+    y: COPY_FORMAT_SELECTOR_RES_POS.y + COPY_FORMAT_SELECTOR_HEIGHT as u32,
+};
+
+pub const PALETTE_CAPACITY: usize = 24;
+pub const PALETTE_COLUMNS: usize = 8;
+pub const PALETTE_SWATCH_WIDTH: u16 = 3;
+pub const PALETTE_STRIP_REL_POS: Vec2 = Vec2 {
+    x: INPUTS_REL_POS.x,
+    y: HISTORY_STRIP_REL_POS.y + 3,
+};
+
+pub const IMAGE_VIEW_REL_POS: Vec2 = SV_PICKER_REL_POS;
+pub const IMAGE_VIEW_HEIGHT: u32 = SV_PICKER_HEIGHT;
+pub const IMAGE_VIEW_PAN_STEP: i32 = 8;
+pub const IMAGE_VIEW_MIN_SCALE: f32 = 1.0 / 32.0;
+pub const IMAGE_VIEW_MAX_SCALE: f32 = 256.0;
+pub const IMAGE_VIEW_MAX_SAMPLE_RADIUS: u32 = 16;
+
+pub const EXTRACT_DEFAULT_COUNT: usize = 8;
+
+pub const TERMINAL_QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+pub const STATUS_BAR_REL_POS: Vec2 = Vec2 {
+    x: 0,
+    y: SV_PICKER_REL_POS.y + SV_PICKER_HEIGHT + 1,
 };
+pub const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(3);
+pub const STATUS_ERROR_DURATION: Duration = Duration::from_secs(5);
+pub const STATUS_MIN_DURATION: Duration = Duration::from_secs(1);
 
 const fn const_max(a: u16, b: u16) -> u16 {
     if a > b { a } else { b }
 }
 
 pub const TOTAL_WIDTH: u32 =
-    INPUTS_REL_POS.x + const_max(COPY_FORMAT_SELECTOR_WIDTH, INPUTS_CB_WIDTH) as u32;
-pub const TOTAL_HEIGHT: u32 = SV_PICKER_REL_POS.y + SV_PICKER_HEIGHT;
+    INPUTS_REL_POS.x + const_max(COPY_FORMAT_SELECTOR_WIDTH, INPUTS_WIDTH) as u32;
+pub const TOTAL_HEIGHT: u32 = STATUS_BAR_REL_POS.y + 1;
 
 // Box drawing characters
 pub const TOP_LEFT_ROUNDED: char = '\u{256d}';
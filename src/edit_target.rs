@@ -0,0 +1,124 @@
+use crate::color_literal::{ColorLiteral, literal_at};
+use crate::utils::write_atomic;
+use palette::Hsv;
+use std::fs;
+use std::io::{self};
+use std::path::PathBuf;
+
+/// Color literal of a file opened with `--edit file:line:col`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditTarget {
+    pub path: PathBuf,
+    /// Line number, from 1
+    pub line: usize,
+    pub literal: ColorLiteral,
+    /// Byte offset of the literal in the file
+    offset: usize,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Splits `file:line:col` or `file:line`, the line and column start at 1 and
+/// the column counts characters.
+pub fn parse_location(location: &str) -> Option<(PathBuf, usize, Option<usize>)> {
+    let (rest, last) = location.rsplit_once(':')?;
+    let last: usize = last.parse().ok()?;
+    if let Some((path, line)) = rest.rsplit_once(':')
+        && let Ok(line) = line.parse::<usize>()
+    {
+        return Some((PathBuf::from(path), line, Some(last)));
+    }
+    Some((PathBuf::from(rest), last, None))
+}
+
+impl EditTarget {
+    pub fn open(location: &str) -> io::Result<EditTarget> {
+        let (path, line, column) = parse_location(location)
+            .ok_or(invalid(format!("expected file:line:col, got {}", location)))?;
+        let content = fs::read_to_string(&path)?;
+        let (offset, text) = content
+            .split_inclusive('\n')
+            .scan(0, |offset, text| {
+                let start = *offset;
+                *offset += text.len();
+                Some((start, text))
+            })
+            .nth(
+                line.checked_sub(1)
+                    .ok_or(invalid("lines start at 1".to_string()))?,
+            )
+            .ok_or(invalid(format!("{} has no line {}", path.display(), line)))?;
+        let column = column.map(|column| {
+            text.char_indices()
+                .nth(column.saturating_sub(1))
+                .map_or(text.len(), |(index, _)| index)
+        });
+        let literal =
+            literal_at(text, column).ok_or(invalid(format!("no color at {}", location)))?;
+        Ok(EditTarget {
+            path,
+            line,
+            offset: offset + literal.span.start,
+            literal,
+        })
+    }
+
+    /// Replaces the literal with the color in the same notation, the rest of
+    /// the file is kept byte for byte. Fails if the literal changed since the
+    /// file was opened.
+    pub fn write(&mut self, color: &Hsv) -> io::Result<String> {
+        let mut content = fs::read(&self.path)?;
+        let range = self.offset..self.offset + self.literal.text.len();
+        if content.get(range.clone()) != Some(self.literal.text.as_bytes()) {
+            return Err(invalid(format!(
+                "{} changed since it was opened",
+                self.path.display()
+            )));
+        }
+        let text = self.literal.rewrite(color);
+        content.splice(range, text.bytes());
+        write_atomic(&self.path, &content)?;
+        // The numbers may not have the same length anymore
+        if let Some(literal) = self.literal.rewritten(color) {
+            self.literal = literal;
+        }
+        Ok(text)
+    }
+}
+
+#[test]
+fn test_edit_target() {
+    let path =
+        std::env::temp_dir().join(format!("tui-color-picker-edit-{}.css", std::process::id()));
+    let content = "body {\r\n  color: #FF5733; /* é */ background: rgb(1, 2, 3);\r\n}";
+    fs::write(&path, content).unwrap();
+
+    let location = format!("{}:2:41", path.display());
+    let mut target = EditTarget::open(&location).unwrap();
+    assert_eq!(target.literal.text, "rgb(1, 2, 3)");
+    let color = crate::utils::hsv_from_rgb(100, 200, 255);
+    assert_eq!(target.write(&color).unwrap(), "rgb(100, 200, 255)");
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        content.replace("rgb(1, 2, 3)", "rgb(100, 200, 255)")
+    );
+    // Written again from the new literal
+    target.write(&crate::utils::hsv_from_rgb(0, 0, 0)).unwrap();
+    assert!(
+        fs::read_to_string(&path)
+            .unwrap()
+            .contains("rgb(0, 0, 0);\r\n}")
+    );
+
+    let target = EditTarget::open(&format!("{}:2", path.display())).unwrap();
+    assert_eq!(target.literal.text, "#FF5733");
+    assert!(EditTarget::open(&format!("{}:1:1", path.display())).is_err());
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        parse_location("C:/theme.css:4:2"),
+        Some((PathBuf::from("C:/theme.css"), 4, Some(2)))
+    );
+}
@@ -0,0 +1,92 @@
+use crate::color_palette::ColorPalette;
+use crate::quantize::DominantColor;
+use crate::utils::hsv_from_rgb;
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Gpl,
+}
+
+impl OutputFormat {
+    pub fn parse(s: &str) -> Option<OutputFormat> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Some(OutputFormat::Text),
+            "json" => Some(OutputFormat::Json),
+            "gpl" => Some(OutputFormat::Gpl),
+            _ => None,
+        }
+    }
+}
+
+fn hex(rgb: (u8, u8, u8)) -> String {
+    format!("#{:02X}{:02X}{:02X}", rgb.0, rgb.1, rgb.2)
+}
+
+/// Labels the swatches with the hex code and the share of the image.
+pub fn palette_from_dominant_colors(colors: &[DominantColor], name: &str) -> ColorPalette {
+    let mut palette = ColorPalette::new(name);
+    for color in colors {
+        let (r, g, b) = color.rgb;
+        let label = format!("{} {:.1}%", hex(color.rgb), color.population * 100.0);
+        palette.push(label, hsv_from_rgb(r, g, b));
+    }
+    palette
+}
+
+pub fn format_dominant_colors(
+    colors: &[DominantColor],
+    format: OutputFormat,
+    name: &str,
+) -> String {
+    match format {
+        OutputFormat::Text => colors
+            .iter()
+            .map(|color| format!("{} {:5.1}%\n", hex(color.rgb), color.population * 100.0))
+            .collect(),
+        OutputFormat::Json => {
+            let colors: Vec<_> = colors
+                .iter()
+                .map(|color| {
+                    json!({
+                        "hex": hex(color.rgb),
+                        "rgb": [color.rgb.0, color.rgb.1, color.rgb.2],
+                        "population": color.population,
+                    })
+                })
+                .collect();
+            let mut out = serde_json::to_string_pretty(&colors).unwrap_or_default();
+            out.push('\n');
+            out
+        }
+        OutputFormat::Gpl => palette_from_dominant_colors(colors, name).to_gpl(),
+    }
+}
+
+#[test]
+fn test_format_dominant_colors() {
+    let colors = [
+        DominantColor {
+            rgb: (255, 87, 51),
+            population: 0.75,
+        },
+        DominantColor {
+            rgb: (0, 0, 0),
+            population: 0.25,
+        },
+    ];
+    assert_eq!(
+        format_dominant_colors(&colors, OutputFormat::Text, "logo"),
+        "#FF5733  75.0%\n#000000  25.0%\n"
+    );
+    assert_eq!(
+        format_dominant_colors(&colors, OutputFormat::Gpl, "logo"),
+        "GIMP Palette\nName: logo\nColumns: 8\n#\n255  87  51\t#FF5733 75.0%\n  0   0   0\t#000000 25.0%\n"
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&format_dominant_colors(&colors, OutputFormat::Json, "logo")).unwrap();
+    assert_eq!(json[0]["hex"], "#FF5733");
+    assert_eq!(json[1]["rgb"], json!([0, 0, 0]));
+}
@@ -0,0 +1,90 @@
+use crate::clipboard::paste::validate_pasted_color;
+use crate::utils::{rgb_from_hsv, write_atomic};
+use palette::Hsv;
+use std::fs;
+use std::io::{self};
+use std::path::PathBuf;
+
+/// Most recently used colors, newest first, saved to a file with one hex
+/// color per line.
+pub struct History {
+    pub colors: Vec<Hsv>,
+    pub path: Option<PathBuf>,
+    pub max_len: usize,
+}
+
+impl History {
+    pub fn new(path: Option<PathBuf>, max_len: usize) -> Self {
+        History {
+            colors: Vec::with_capacity(max_len),
+            path,
+            max_len,
+        }
+    }
+
+    /// Loads the history from its file. A missing file is an empty history,
+    /// invalid lines are skipped.
+    pub fn load(path: PathBuf, max_len: usize) -> io::Result<Self> {
+        let mut history = History::new(Some(path), max_len);
+        let path = history.path.as_deref().unwrap();
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(history),
+            Err(err) => return Err(err),
+        };
+        history.colors = content
+            .lines()
+            .filter_map(|line| validate_pasted_color(line.trim()))
+            .take(max_len)
+            .collect();
+        Ok(history)
+    }
+
+    /// Adds a color at the front. Returns false if it is already the most
+    /// recent color, consecutive duplicates are collapsed.
+    pub fn push(&mut self, color: Hsv) -> bool {
+        if let Some(last) = self.colors.first()
+            && rgb_from_hsv(last) == rgb_from_hsv(&color)
+        {
+            return false;
+        }
+        self.colors.insert(0, color);
+        self.colors.truncate(self.max_len);
+        true
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut content = String::with_capacity(self.colors.len() * 8);
+        for color in &self.colors {
+            let (r, g, b) = rgb_from_hsv(color);
+            content.push_str(&format!("#{:02X}{:02X}{:02X}\n", r, g, b));
+        }
+        write_atomic(path, content.as_bytes())
+    }
+}
+
+#[test]
+fn test_history_persistence() {
+    use crate::utils::hsv_from_rgb;
+
+    let path =
+        std::env::temp_dir().join(format!("tui-color-picker-history-{}", std::process::id()));
+    let mut history = History::new(Some(path.clone()), 3);
+    assert!(history.push(hsv_from_rgb(255, 0, 0)));
+    assert!(!history.push(hsv_from_rgb(255, 0, 0)));
+    assert!(history.push(hsv_from_rgb(0, 255, 0)));
+    assert!(history.push(hsv_from_rgb(255, 0, 0)));
+    assert!(history.push(hsv_from_rgb(0, 0, 255)));
+    history.save().unwrap();
+
+    let loaded = History::load(path.clone(), 3).unwrap();
+    let _ = fs::remove_file(&path);
+    let loaded: Vec<_> = loaded.colors.iter().map(rgb_from_hsv).collect();
+    assert_eq!(loaded, vec![(0, 0, 255), (255, 0, 0), (0, 255, 0)]);
+}
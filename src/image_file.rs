@@ -0,0 +1,49 @@
+use image::RgbImage;
+use std::io::{self};
+use std::path::Path;
+
+/// Loads a PNG, JPEG or PPM file as 8-bit RGB.
+pub fn load_image(path: &Path) -> io::Result<RgbImage> {
+    let image = image::ImageReader::open(path)?
+        .with_guessed_format()?
+        .decode()
+        .map_err(io::Error::other)?;
+    Ok(image.to_rgb8())
+}
+
+/// Average color of the square of side `2 * radius + 1` centered on the
+/// pixel, clipped to the image bounds.
+pub fn average_pixels(image: &RgbImage, x: u32, y: u32, radius: u32) -> (u8, u8, u8) {
+    let x_range = x.saturating_sub(radius)..=x.saturating_add(radius).min(image.width() - 1);
+    let y_range = y.saturating_sub(radius)..=y.saturating_add(radius).min(image.height() - 1);
+    let mut sum = [0u64; 3];
+    let mut count = 0u64;
+    for py in y_range {
+        for px in x_range.clone() {
+            let pixel = image.get_pixel(px, py);
+            for (total, channel) in sum.iter_mut().zip(pixel.0) {
+                *total += channel as u64;
+            }
+            count += 1;
+        }
+    }
+    let average = |total: u64| ((total + count / 2) / count) as u8;
+    (average(sum[0]), average(sum[1]), average(sum[2]))
+}
+
+#[test]
+fn test_average_pixels() {
+    let image = RgbImage::from_fn(3, 3, |x, y| {
+        if x == 1 && y == 1 {
+            image::Rgb([255, 0, 90])
+        } else {
+            image::Rgb([0, 0, 0])
+        }
+    });
+    assert_eq!(average_pixels(&image, 1, 1, 0), (255, 0, 90));
+    assert_eq!(average_pixels(&image, 1, 1, 1), (28, 0, 10));
+    // Only the 2x2 square inside the image is averaged in the corner
+    assert_eq!(average_pixels(&image, 0, 0, 1), (64, 0, 23));
+    // The whole image, without overflowing
+    assert_eq!(average_pixels(&image, 2, 2, u32::MAX), (28, 0, 10));
+}
@@ -0,0 +1,320 @@
+use crate::clipboard::paste::validate_pasted_color;
+use crate::utils::rgb_from_hsv;
+use palette::Hsv;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::Duration;
+
+/// Clients that do not read their messages are dropped after this delay
+const WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Requests of the JSON-lines protocol, one object per line with a `cmd`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    /// `{"cmd": "set", "color": "#ff5733"}`, any format accepted by paste
+    Set(Hsv),
+    /// `{"cmd": "get"}`
+    Get,
+    /// `{"cmd": "subscribe"}`, then every change is sent as an event
+    Subscribe,
+    /// `{"cmd": "close"}` quits the picker, subscribers get the final color
+    Close,
+}
+
+enum Message {
+    Connected(usize, UnixStream),
+    Request(usize, Result<Request, String>, Value),
+    Disconnected(usize),
+}
+
+struct Client {
+    stream: UnixStream,
+    subscribed: bool,
+}
+
+/// Unix socket server driven from the main loop. Connections are accepted and
+/// read on background threads, requests are handled by `State::tick` through
+/// `poll` and the replies are written from the main thread.
+pub struct IpcServer {
+    pub path: PathBuf,
+    receiver: Receiver<Message>,
+    clients: HashMap<usize, Client>,
+    /// Clients that stopped sending, dropped once their replies are written
+    disconnected: Vec<usize>,
+}
+
+pub fn parse_request(line: &str) -> Result<Request, String> {
+    let value: Value = serde_json::from_str(line).map_err(|err| err.to_string())?;
+    match value["cmd"].as_str() {
+        Some("set") => {
+            let color = value["color"].as_str().ok_or("set requires a color")?;
+            validate_pasted_color(color.trim())
+                .map(Request::Set)
+                .ok_or(format!("invalid color: {}", color))
+        }
+        Some("get") => Ok(Request::Get),
+        Some("subscribe") => Ok(Request::Subscribe),
+        Some("close") => Ok(Request::Close),
+        Some(cmd) => Err(format!("unknown command: {}", cmd)),
+        None => Err("missing cmd".to_string()),
+    }
+}
+
+/// Color as sent to clients, in hex and RGB
+pub fn color_json(color: &Hsv) -> Value {
+    let (r, g, b) = rgb_from_hsv(color);
+    json!({
+        "color": format!("#{:02x}{:02x}{:02x}", r, g, b),
+        "rgb": [r, g, b],
+    })
+}
+
+fn read_requests(id: usize, stream: UnixStream, sender: Sender<Message>) {
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        // The request id is echoed back so clients can match the replies
+        let request_id = serde_json::from_str::<Value>(&line)
+            .map(|value| value["id"].clone())
+            .unwrap_or(Value::Null);
+        if sender
+            .send(Message::Request(id, parse_request(&line), request_id))
+            .is_err()
+        {
+            return;
+        }
+    }
+    let _ = sender.send(Message::Disconnected(id));
+}
+
+impl IpcServer {
+    /// Listens on `path`. A socket file left by a picker that did not exit
+    /// cleanly is replaced, a live one or any other file is an error.
+    pub fn bind(path: &Path) -> io::Result<IpcServer> {
+        if let Ok(metadata) = fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{} exists and is not a socket", path.display()),
+                ));
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "another picker is listening",
+                ));
+            }
+            fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            for (id, stream) in listener.incoming().enumerate() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let Ok(reader) = stream.try_clone() else {
+                    continue;
+                };
+                if sender.send(Message::Connected(id, stream)).is_err() {
+                    return;
+                }
+                let sender = sender.clone();
+                thread::spawn(move || read_requests(id, reader, sender));
+            }
+        });
+        Ok(IpcServer {
+            path: path.to_path_buf(),
+            receiver,
+            clients: HashMap::new(),
+            disconnected: Vec::new(),
+        })
+    }
+
+    /// Returns the requests received since the last call with the client they
+    /// came from and their `id`, invalid ones as the error to reply. A client
+    /// that closed its end after sending is kept until the next call, so the
+    /// replies to its last requests still reach it.
+    pub fn poll(&mut self) -> Vec<(usize, Result<Request, String>, Value)> {
+        for id in self.disconnected.drain(..) {
+            self.clients.remove(&id);
+        }
+        let mut requests = Vec::new();
+        while let Ok(message) = self.receiver.try_recv() {
+            match message {
+                Message::Connected(id, stream) => {
+                    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                    let client = Client {
+                        stream,
+                        subscribed: false,
+                    };
+                    self.clients.insert(id, client);
+                }
+                Message::Request(id, request, request_id) => {
+                    requests.push((id, request, request_id))
+                }
+                Message::Disconnected(id) => self.disconnected.push(id),
+            }
+        }
+        requests
+    }
+
+    pub fn subscribe(&mut self, id: usize) {
+        if let Some(client) = self.clients.get_mut(&id) {
+            client.subscribed = true;
+        }
+    }
+
+    /// Answers a request with `{"ok": true, ...}` or `{"ok": false, "error"}`
+    pub fn reply(&mut self, id: usize, request_id: &Value, result: Result<Value, String>) {
+        let mut reply = match result {
+            Ok(Value::Object(fields)) => {
+                let mut reply = json!({ "ok": true });
+                reply.as_object_mut().unwrap().extend(fields);
+                reply
+            }
+            Ok(_) => json!({ "ok": true }),
+            Err(error) => json!({ "ok": false, "error": error }),
+        };
+        if !request_id.is_null() {
+            reply["id"] = request_id.clone();
+        }
+        self.send(id, &reply);
+    }
+
+    /// Sends `{"event": name, ...}` to every subscriber
+    pub fn broadcast(&mut self, event: &str, fields: Value) {
+        let mut message = json!({ "event": event });
+        if let Value::Object(fields) = fields {
+            message.as_object_mut().unwrap().extend(fields);
+        }
+        let subscribers: Vec<_> = self
+            .clients
+            .iter()
+            .filter(|(_, client)| client.subscribed)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in subscribers {
+            self.send(id, &message);
+        }
+    }
+
+    fn send(&mut self, id: usize, message: &Value) {
+        let Some(client) = self.clients.get_mut(&id) else {
+            return;
+        };
+        let line = format!("{}\n", message);
+        if client.stream.write_all(line.as_bytes()).is_err() {
+            self.clients.remove(&id);
+        }
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[test]
+fn test_ipc_server() {
+    let path = std::env::temp_dir().join(format!("tui-color-picker-{}.sock", std::process::id()));
+    let mut server = IpcServer::bind(&path).unwrap();
+    let client = UnixStream::connect(&path).unwrap();
+    let mut lines = BufReader::new(client.try_clone().unwrap()).lines();
+    let mut writer = client;
+    writer
+        .write_all(b"{\"cmd\": \"subscribe\"}\n{\"cmd\": \"set\", \"color\": \"#FF5733\", \"id\": 7}\n{\"cmd\": \"frobnicate\"}\n")
+        .unwrap();
+
+    let mut requests = Vec::new();
+    for _ in 0..100 {
+        requests.extend(server.poll());
+        if requests.len() == 3 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(requests[0].1, Ok(Request::Subscribe));
+    let Ok(Request::Set(color)) = requests[1].1 else {
+        panic!("expected a set request");
+    };
+    server.subscribe(requests[0].0);
+    server.reply(requests[1].0, &requests[1].2, Ok(color_json(&color)));
+    server.reply(
+        requests[2].0,
+        &requests[2].2,
+        requests[2].1.clone().map(|_| Value::Null),
+    );
+    server.broadcast("change", color_json(&color));
+
+    let reply: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+    assert_eq!(
+        reply,
+        json!({"ok": true, "id": 7, "color": "#ff5733", "rgb": [255, 87, 51]})
+    );
+    let error: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+    assert_eq!(error["ok"], false);
+    let event: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+    assert_eq!(event["event"], "change");
+
+    drop(server);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_ipc_bind_existing_file() {
+    let path = std::env::temp_dir().join(format!("tui-color-picker-{}.txt", std::process::id()));
+    fs::write(&path, "notes").unwrap();
+    let error = IpcServer::bind(&path).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "notes");
+    fs::remove_file(&path).unwrap();
+
+    // A stale socket is replaced
+    let path = std::env::temp_dir().join(format!(
+        "tui-color-picker-{}-stale.sock",
+        std::process::id()
+    ));
+    drop(UnixListener::bind(&path).unwrap());
+    assert!(IpcServer::bind(&path).is_ok());
+}
+
+#[test]
+fn test_ipc_half_closed_client() {
+    let path =
+        std::env::temp_dir().join(format!("tui-color-picker-{}-half.sock", std::process::id()));
+    let mut server = IpcServer::bind(&path).unwrap();
+    let mut client = UnixStream::connect(&path).unwrap();
+    client.write_all(b"{\"cmd\": \"get\"}\n").unwrap();
+    client.shutdown(std::net::Shutdown::Write).unwrap();
+
+    // Wait for the disconnection to be in the same batch as the request
+    let mut requests = Vec::new();
+    for _ in 0..100 {
+        thread::sleep(Duration::from_millis(10));
+        requests.extend(server.poll());
+        if !server.disconnected.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(requests[0].1, Ok(Request::Get));
+    server.reply(requests[0].0, &requests[0].2, Ok(Value::Null));
+    let mut reply = String::new();
+    BufReader::new(&client).read_line(&mut reply).unwrap();
+    assert_eq!(reply, "{\"ok\":true}\n");
+
+    server.poll();
+    assert!(server.clients.is_empty());
+}
@@ -1,7 +1,28 @@
+pub mod backend;
+pub mod cie;
+pub mod cli;
 pub mod clipboard;
+pub mod color_literal;
+pub mod color_model;
+pub mod color_palette;
+pub mod color_space;
+pub mod commands;
 pub mod constants;
 pub mod crossterm_commands;
+pub mod edit_target;
+pub mod extract;
+pub mod history;
+pub mod image_file;
+#[cfg(unix)]
+pub mod ipc;
+pub mod palette_file;
+pub mod pixel_mouse;
+pub mod quantize;
+pub mod render;
+pub mod scan;
+pub mod scheme;
 pub mod state;
+pub mod terminal_palette;
 pub mod types;
 pub mod ui;
 pub mod utils;
@@ -1,38 +1,229 @@
 use std::io::{self};
 
 use crossterm::event::*;
+use std::path::PathBuf;
 use std::time::Duration;
-use tui_color_picker::clipboard::ui::clear_clipboard_format_selector;
+use tui_color_picker::backend::TerminalBackend;
+use tui_color_picker::cli::{Command, ExportArgs, ExtractArgs, ScanArgs, USAGE, parse_args};
 use tui_color_picker::constants::*;
+use tui_color_picker::edit_target::EditTarget;
+use tui_color_picker::extract::format_dominant_colors;
+use tui_color_picker::history::History;
+use tui_color_picker::image_file::load_image;
+#[cfg(unix)]
+use tui_color_picker::ipc::IpcServer;
+use tui_color_picker::pixel_mouse::{CellSize, PIXEL_MOUSE_QUERY, parse_pixel_mouse_replies};
+use tui_color_picker::quantize::dominant_colors;
+use tui_color_picker::scan::{format_matches, scan_text};
+use tui_color_picker::scheme::Scheme;
 use tui_color_picker::state::*;
+use tui_color_picker::terminal_palette::{
+    TERMINAL_PALETTE_NAME, palette_query, parse_replies, query_terminal, query_terminal_palette,
+};
 use tui_color_picker::ui::hue_picker::HuePicker;
 use tui_color_picker::ui::inputs::Inputs;
 use tui_color_picker::ui::saturation_value_picker::SaturationValuePicker;
+use tui_color_picker::ui::status_bar::{MessageLevel, StatusBar};
+use tui_color_picker::ui::swatch_strip::SwatchStrip;
+use tui_color_picker::utils::{app_state_dir, coalesce_drags, write_atomic};
+
+fn run_extract(args: ExtractArgs) -> io::Result<()> {
+    let image = load_image(&args.image)?;
+    let colors = dominant_colors(&image, args.count, args.seed);
+    let name = args
+        .image
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    print!("{}", format_dominant_colors(&colors, args.format, &name));
+    Ok(())
+}
+
+fn run_export(args: ExportArgs) -> io::Result<()> {
+    crossterm::terminal::enable_raw_mode()?;
+    let terminal_palette = query_terminal_palette(TERMINAL_QUERY_TIMEOUT);
+    crossterm::terminal::disable_raw_mode()?;
+    let terminal_palette = terminal_palette?;
+    if terminal_palette.is_empty() {
+        return Err(io::Error::other("the terminal did not report its palette"));
+    }
+    let name = args
+        .name
+        .or(args
+            .output
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned()))
+        .unwrap_or(TERMINAL_PALETTE_NAME.to_string());
+    let scheme = Scheme::from_terminal_palette(&terminal_palette, name);
+    let content = args.format.write(&scheme);
+    match &args.output {
+        Some(path) => write_atomic(path, content.as_bytes()),
+        None => {
+            print!("{}", content);
+            Ok(())
+        }
+    }
+}
+
+/// Exits like grep: 0 when colors were found, 1 when none were and 2 when a
+/// file could not be read.
+fn run_scan(mut args: ScanArgs) -> io::Result<()> {
+    if args.files.is_empty() {
+        args.files.push(PathBuf::from("-"));
+    }
+    let mut matches = Vec::new();
+    let mut failed = false;
+    for path in &args.files {
+        let content = if path.as_os_str() == "-" {
+            io::read_to_string(io::stdin())
+        } else {
+            std::fs::read_to_string(path)
+        };
+        let source = if path.as_os_str() == "-" {
+            "(standard input)".to_string()
+        } else {
+            path.display().to_string()
+        };
+        match content {
+            Ok(content) => matches.extend(scan_text(&source, &content)),
+            Err(err) => {
+                eprintln!("error: {}: {}", source, err);
+                failed = true;
+            }
+        }
+    }
+    print!("{}", format_matches(&matches, args.format));
+    match (failed, matches.is_empty()) {
+        (true, _) => std::process::exit(2),
+        (false, true) => std::process::exit(1),
+        (false, false) => Ok(()),
+    }
+}
 
 fn main() -> io::Result<()> {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Pick(args)) => args,
+        Ok(Command::Extract(args)) => return run_extract(args),
+        Ok(Command::Export(args)) => return run_export(args),
+        Ok(Command::Scan(args)) => return run_scan(args),
+        Err(message) => {
+            if message.is_empty() {
+                print!("{}", USAGE);
+                return Ok(());
+            }
+            eprint!("error: {}\n\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // Fail before taking over the terminal
+    let edit_target = match args.edit.as_deref().map(EditTarget::open) {
+        Some(Ok(target)) => Some(target),
+        Some(Err(err)) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
     let (term_width, term_height) = crossterm::terminal::size()?;
 
     let mut app = State::new(
         SaturationValuePicker::new(SV_PICKER_REL_POS, SV_PICKER_WIDTH, SV_PICKER_HEIGHT),
         HuePicker::new(HUE_PICKER_REL_POS, HUE_PICKER_WIDTH, HUE_PICKER_HEIGHT),
         Inputs::new(INPUTS_REL_POS),
+        StatusBar::new(STATUS_BAR_REL_POS, TOTAL_WIDTH),
+        SwatchStrip::new(
+            HISTORY_STRIP_REL_POS,
+            "Recent:",
+            HISTORY_SWATCH_WIDTH,
+            HISTORY_LEN,
+            HISTORY_LEN,
+            true,
+        ),
         term_width,
         term_height,
+        Box::new(TerminalBackend),
     )?;
+    app.selection = args.selection;
+    app.color_settings = args.color_settings;
+    app.inputs.step = args.step;
+    app.inputs.big_step = args.big_step;
+    let query = format!("{}{}", palette_query(), PIXEL_MOUSE_QUERY);
+    let replies = query_terminal(&query, TERMINAL_QUERY_TIMEOUT);
+    let terminal_palette = replies.as_deref().map(parse_replies);
+    if let Ok(replies) = &replies
+        && let (true, cell_size) = parse_pixel_mouse_replies(replies)
+        && let Some(cell) = cell_size.or_else(CellSize::from_window)
+    {
+        app.enable_pixel_mouse(cell)?;
+    }
+    app.image_view.sample_radius = args.sample_radius;
+    let history_error = match app_state_dir() {
+        Some(dir) => match History::load(dir.join("history"), HISTORY_LEN) {
+            Ok(history) => {
+                app.history = history;
+                None
+            }
+            Err(err) => Some(format!("Could not load history: {}", err)),
+        },
+        None => Some("No state directory, history won't be saved".to_string()),
+    };
     app.update_offset(term_width, term_height);
     app.offset_all();
     app.draw(false)?;
+    if let Some(message) = history_error {
+        app.notify(MessageLevel::Error, message)?;
+    }
+    match terminal_palette {
+        Ok(terminal_palette) if !terminal_palette.is_empty() => {
+            app.terminal_palette = terminal_palette;
+            app.load_palette(app.terminal_palette.to_palette())?;
+        }
+        Ok(_) => {}
+        Err(err) => app.notify(
+            MessageLevel::Error,
+            format!("Could not query the terminal palette: {}", err),
+        )?,
+    }
+    if let Some(path) = &args.listen {
+        #[cfg(unix)]
+        match IpcServer::bind(path) {
+            Ok(server) => app.ipc = Some(server),
+            Err(err) => app.notify(
+                MessageLevel::Error,
+                format!("Could not listen on {}: {}", path.display(), err),
+            )?,
+        }
+        #[cfg(not(unix))]
+        app.notify(
+            MessageLevel::Error,
+            format!("Cannot listen on {}, sockets need Unix", path.display()),
+        )?;
+    }
+    if let Some(path) = &args.palette {
+        app.import_palette(path)?;
+    }
+    if let Some(path) = &args.image {
+        app.open_image(path)?;
+    }
+    if let Some(target) = edit_target {
+        app.open_edit_target(target)?;
+    }
+
+    app.present()?;
 
-    loop {
+    'main: loop {
+        let mut events = Vec::new();
         if poll(Duration::from_millis(100))? {
-            // clear confirmation message after next event
-            if app.flags & COPY_CONFIRMED_FLAG != 0 || app.flags & PASTE_CONFIRMED_FLAG != 0 {
-                clear_clipboard_format_selector(COPY_FORMAT_SELECTOR_RES_POS + app.offset)?;
-                app.flags &= !COPY_CONFIRMED_FLAG;
-                app.flags &= !PASTE_CONFIRMED_FLAG;
+            events.push(read()?);
+            // Everything already received is handled before drawing a frame
+            while poll(Duration::ZERO)? {
+                events.push(read()?);
             }
-
-            let event = read()?;
+        }
+        for event in coalesce_drags(events) {
             match event {
                 Event::Mouse(event) if !app.term_too_small => {
                     app.handle_mouse_event(event)?;
@@ -40,15 +231,23 @@ fn main() -> io::Result<()> {
                 Event::Key(event) if !app.term_too_small => {
                     app.handle_key_event(event)?;
                     if app.flags & EXIT_FLAG != 0 {
-                        break;
+                        break 'main;
                     }
                 }
+                Event::Paste(text) if !app.term_too_small => {
+                    app.handle_paste_event(&text)?;
+                }
                 Event::Resize(x, y) => {
                     app.handle_resize(x, y)?;
                 }
                 _ => {}
             }
         }
+        app.tick()?;
+        if app.flags & EXIT_FLAG != 0 {
+            break;
+        }
+        app.present()?;
     }
     Ok(())
 }
@@ -0,0 +1,215 @@
+use super::{FoundColor, HexSpan, join_lines, split_lines};
+use crate::color_palette::ColorPalette;
+use crate::utils::hsv_from_rgb;
+use std::io::{self};
+
+/// Roles of the base16 slots followed by the base24 extension
+const SLOT_ROLES: [&str; 24] = [
+    "background",
+    "lighter background",
+    "selection",
+    "comments",
+    "dark foreground",
+    "foreground",
+    "light foreground",
+    "light background",
+    "red",
+    "orange",
+    "yellow",
+    "green",
+    "cyan",
+    "blue",
+    "magenta",
+    "brown",
+    "darker background",
+    "darkest background",
+    "bright red",
+    "bright yellow",
+    "bright green",
+    "bright cyan",
+    "bright blue",
+    "bright magenta",
+];
+
+/// base16 or base24 scheme, in the legacy flat layout or the tinted-theming
+/// one with a `palette` map. The file is kept line by line and only the hex
+/// digits of the slots are rewritten, so the metadata, quoting and comments
+/// survive an edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Base16Scheme {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    lines: Vec<String>,
+    slots: Vec<FoundColor>,
+}
+
+fn slot_index(key: &str) -> Option<usize> {
+    let digits = key.strip_prefix("base")?;
+    if digits.len() != 2 {
+        return None;
+    }
+    let index = usize::from_str_radix(digits, 16).ok()?;
+    (index < SLOT_ROLES.len()).then_some(index)
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.split(quote).next())
+        {
+            return inner;
+        }
+    }
+    value.split(" #").next().unwrap_or(value).trim()
+}
+
+impl Base16Scheme {
+    /// Needs at least base00 to base0F, a scheme with base10 to base17 too is
+    /// a base24 scheme.
+    pub fn parse(content: &str) -> io::Result<Base16Scheme> {
+        let mut scheme = Base16Scheme {
+            name: None,
+            author: None,
+            lines: split_lines(content),
+            slots: Vec::new(),
+        };
+        let mut slots: [Option<FoundColor>; 24] = Default::default();
+        for (i, line) in scheme.lines.iter().enumerate() {
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value_start = key.len() + 1;
+            match key.trim() {
+                "scheme" | "name" => scheme.name = Some(unquote(value).to_string()),
+                "author" => scheme.author = Some(unquote(value).to_string()),
+                key => {
+                    let Some(index) = slot_index(key) else {
+                        continue;
+                    };
+                    slots[index] =
+                        Some(HexSpan::find(line, i, value_start).ok_or(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("invalid color for {} on line {}", key, i + 1),
+                        ))?);
+                }
+            }
+        }
+        let count = if slots[16..].iter().all(Option::is_some) {
+            24
+        } else {
+            16
+        };
+        for (index, slot) in slots.into_iter().take(count).enumerate() {
+            scheme.slots.push(slot.ok_or(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("missing base{:02X}", index),
+            ))?);
+        }
+        Ok(scheme)
+    }
+
+    pub fn is_base24(&self) -> bool {
+        self.slots.len() == 24
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = (u8, u8, u8)> + '_ {
+        self.slots.iter().map(|(rgb, _)| *rgb)
+    }
+
+    pub fn set(&mut self, index: usize, rgb: (u8, u8, u8)) {
+        let Some((color, span)) = self.slots.get_mut(index) else {
+            return;
+        };
+        *color = rgb;
+        span.replace(&mut self.lines, rgb);
+    }
+
+    /// Swatches labeled with the slot and its role
+    pub fn to_palette(&self, fallback_name: &str) -> ColorPalette {
+        let mut palette = ColorPalette::new(self.name.as_deref().unwrap_or(fallback_name));
+        for (index, (r, g, b)) in self.colors().enumerate() {
+            palette.push(
+                format!("base{:02X} ({})", index, SLOT_ROLES[index]),
+                hsv_from_rgb(r, g, b),
+            );
+        }
+        palette
+    }
+
+    pub fn to_yaml(&self) -> String {
+        join_lines(&self.lines)
+    }
+}
+
+#[test]
+fn test_base16_round_trip() {
+    let legacy = "\
+# Comments are kept
+scheme: \"Gruvbox dark, medium\"
+author: \"Dawid Kurek (dawikur@gmail.com)\"
+base00: \"282828\" # ----
+base01: \"3c3836\"
+base02: \"504945\"
+base03: \"665c54\"
+base04: \"bdae93\"
+base05: \"d5c4a1\"
+base06: \"ebdbb2\"
+base07: \"fbf1c7\"
+base08: \"fb4934\"
+base09: \"fe8019\"
+base0A: \"fabd2f\"
+base0B: \"b8bb26\"
+base0C: \"8ec07c\"
+base0D: \"83a598\"
+base0E: \"d3869b\"
+base0F: \"d65d0e\"
+";
+    let mut scheme = Base16Scheme::parse(legacy).unwrap();
+    assert_eq!(scheme.name.as_deref(), Some("Gruvbox dark, medium"));
+    assert!(!scheme.is_base24());
+    assert_eq!(scheme.to_yaml(), legacy);
+    assert_eq!(
+        scheme.to_palette("gruvbox").swatches[8].label,
+        "base08 (red)"
+    );
+    scheme.set(0, (0x1d, 0x20, 0x21));
+    assert_eq!(
+        scheme.to_yaml(),
+        legacy.replace("base00: \"282828\"", "base00: \"1d2021\"")
+    );
+
+    let mut tinted =
+        String::from("system: \"base24\"\nname: 'Tinted'\nauthor: someone\npalette:\n");
+    for index in 0..24 {
+        tinted.push_str(&format!(
+            "  base{:02X}: \"#{:06X}\"\n",
+            index,
+            index * 0x0a0a0a
+        ));
+    }
+    let mut scheme = Base16Scheme::parse(&tinted).unwrap();
+    assert!(scheme.is_base24());
+    assert_eq!(scheme.author.as_deref(), Some("someone"));
+    scheme.set(23, (0xab, 0xcd, 0xef));
+    assert!(scheme.to_yaml().contains("  base17: \"#ABCDEF\"\n"));
+    assert_eq!(scheme.colors().nth(1), Some((0x0a, 0x0a, 0x0a)));
+
+    assert!(Base16Scheme::parse("scheme: \"empty\"\nbase00: \"282828\"\n").is_err());
+
+    // Line endings and the missing final newline are kept
+    let crlf = legacy.replace('\n', "\r\n");
+    let crlf = crlf.trim_end();
+    let mut scheme = Base16Scheme::parse(crlf).unwrap();
+    assert_eq!(scheme.name.as_deref(), Some("Gruvbox dark, medium"));
+    assert_eq!(scheme.to_yaml(), crlf);
+    scheme.set(15, (0x1d, 0x20, 0x21));
+    assert_eq!(
+        scheme.to_yaml(),
+        crlf.replace("base0F: \"d65d0e\"", "base0F: \"1d2021\"")
+    );
+}
@@ -0,0 +1,235 @@
+pub mod base16;
+pub mod wal;
+pub mod xresources;
+
+use crate::color_palette::ColorPalette;
+use crate::terminal_palette::Slot;
+use crate::utils::hsv_from_rgb;
+use base16::Base16Scheme;
+use std::fs;
+use std::io::{self};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Position of a hex color in a line of a theme file. Edits only replace the
+/// digits, so the rest of the file stays as it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexSpan {
+    line: usize,
+    digits: Range<usize>,
+    uppercase: bool,
+}
+
+/// Color read from a theme file, with where to write it back
+pub type FoundColor = ((u8, u8, u8), HexSpan);
+
+impl HexSpan {
+    /// Reads the 6 digit color, optionally quoted and prefixed with `#`, at
+    /// `start` in the line, after any whitespace.
+    pub fn find(text: &str, line: usize, start: usize) -> Option<FoundColor> {
+        let value = text.get(start..)?;
+        let mut start = start + value.len() - value.trim_start().len();
+        for prefix in ['"', '\'', '#'] {
+            if text[start..].starts_with(prefix) {
+                start += 1;
+            }
+        }
+        let digits = text.get(start..start + 6)?;
+        let followed_by_digit = text[start + 6..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_hexdigit());
+        if followed_by_digit || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let rgb = u32::from_str_radix(digits, 16).ok()?;
+        let span = HexSpan {
+            line,
+            digits: start..start + 6,
+            uppercase: digits.chars().any(|c| c.is_ascii_uppercase()),
+        };
+        Some((((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8), span))
+    }
+
+    /// Writes the color in place, keeping the case of the original digits
+    pub fn replace(&self, lines: &mut [String], (r, g, b): (u8, u8, u8)) {
+        let mut hex = format!("{:02x}{:02x}{:02x}", r, g, b);
+        if self.uppercase {
+            hex.make_ascii_uppercase();
+        }
+        lines[self.line].replace_range(self.digits.clone(), &hex);
+    }
+}
+
+/// Splits a theme file in lines that keep their own line ending, `\n` or
+/// `\r\n`, so joining them gives the file back byte for byte. The lines are
+/// numbered like the ones of `str::lines`.
+pub fn split_lines(content: &str) -> Vec<String> {
+    content.split_inclusive('\n').map(str::to_string).collect()
+}
+
+pub fn join_lines(lines: &[String]) -> String {
+    lines.concat()
+}
+
+/// Terminal colors of a theme file, in the order of the terminal slots. A slot
+/// set several times in the file is rewritten everywhere.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SlotColors {
+    pub name: Option<String>,
+    lines: Vec<String>,
+    colors: Vec<SlotColor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SlotColor {
+    slot: Slot,
+    rgb: (u8, u8, u8),
+    spans: Vec<HexSpan>,
+}
+
+impl SlotColors {
+    fn new(content: &str) -> SlotColors {
+        SlotColors {
+            name: None,
+            lines: split_lines(content),
+            colors: Vec::new(),
+        }
+    }
+
+    /// The first color found for a slot is the one shown
+    fn push(&mut self, slot: Slot, rgb: (u8, u8, u8), span: HexSpan) {
+        match self.colors.iter_mut().find(|color| color.slot == slot) {
+            Some(color) => color.spans.push(span),
+            None => self.colors.push(SlotColor {
+                slot,
+                rgb,
+                spans: vec![span],
+            }),
+        }
+    }
+
+    /// Sorts the slots like the terminal palette, ANSI colors first
+    fn sort(&mut self) {
+        let order = |slot: &Slot| match slot {
+            Slot::Ansi(index) => *index,
+            Slot::Foreground => 16,
+            Slot::Background => 17,
+            Slot::Cursor => 18,
+        };
+        self.colors.sort_by_key(|color| order(&color.slot));
+    }
+
+    pub fn get(&self, slot: Slot) -> Option<(u8, u8, u8)> {
+        self.colors
+            .iter()
+            .find(|color| color.slot == slot)
+            .map(|color| color.rgb)
+    }
+
+    pub fn set(&mut self, index: usize, rgb: (u8, u8, u8)) {
+        let Some(color) = self.colors.get_mut(index) else {
+            return;
+        };
+        color.rgb = rgb;
+        for span in &color.spans {
+            span.replace(&mut self.lines, rgb);
+        }
+    }
+
+    pub fn to_palette(&self, fallback_name: &str) -> ColorPalette {
+        let mut palette = ColorPalette::new(self.name.as_deref().unwrap_or(fallback_name));
+        for color in &self.colors {
+            let (r, g, b) = color.rgb;
+            palette.push(color.slot.name(), hsv_from_rgb(r, g, b));
+        }
+        palette
+    }
+
+    pub fn serialize(&self) -> String {
+        join_lines(&self.lines)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteFormat {
+    Base16(Base16Scheme),
+    /// pywal or wallust `colors.json`
+    Wal(SlotColors),
+    Xresources(SlotColors),
+}
+
+/// Palette imported from a theme file, the swatches can be edited and written
+/// back to the file in its own format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteFile {
+    pub path: PathBuf,
+    pub format: PaletteFormat,
+}
+
+impl PaletteFile {
+    /// Picks the format from the extension, `.json` files are pywal colors
+    /// and `.yaml` files base16 schemes. Other files are tried as base16
+    /// schemes, then as X resources.
+    pub fn load(path: &Path) -> io::Result<PaletteFile> {
+        let content = fs::read_to_string(path)?;
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        let format = match extension.as_deref() {
+            Some("json") => PaletteFormat::Wal(wal::parse(&content)?),
+            Some("yaml" | "yml") => PaletteFormat::Base16(Base16Scheme::parse(&content)?),
+            _ => match Base16Scheme::parse(&content) {
+                Ok(scheme) => PaletteFormat::Base16(scheme),
+                Err(_) => PaletteFormat::Xresources(xresources::parse(&content)?),
+            },
+        };
+        Ok(PaletteFile {
+            path: path.to_path_buf(),
+            format,
+        })
+    }
+
+    /// Palette named after the scheme, or the file when it has no name
+    pub fn to_palette(&self) -> ColorPalette {
+        let file_name = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match &self.format {
+            PaletteFormat::Base16(scheme) => scheme.to_palette(&file_name),
+            PaletteFormat::Wal(colors) | PaletteFormat::Xresources(colors) => {
+                colors.to_palette(&file_name)
+            }
+        }
+    }
+
+    /// Changes the color of the swatch at `index`
+    pub fn set(&mut self, index: usize, rgb: (u8, u8, u8)) {
+        match &mut self.format {
+            PaletteFormat::Base16(scheme) => scheme.set(index, rgb),
+            PaletteFormat::Wal(colors) | PaletteFormat::Xresources(colors) => {
+                colors.set(index, rgb)
+            }
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        match &self.format {
+            PaletteFormat::Base16(scheme) => scheme.to_yaml(),
+            PaletteFormat::Wal(colors) | PaletteFormat::Xresources(colors) => colors.serialize(),
+        }
+    }
+}
+
+#[test]
+fn test_hex_span() {
+    let mut lines = vec!["  \"color1\": \"#CC241D\",".to_string()];
+    let (rgb, span) = HexSpan::find(&lines[0], 0, 11).unwrap();
+    assert_eq!(rgb, (0xcc, 0x24, 0x1d));
+    span.replace(&mut lines, (0xfb, 0x49, 0x34));
+    assert_eq!(lines[0], "  \"color1\": \"#FB4934\",");
+    assert!(HexSpan::find("#12345678", 0, 0).is_none());
+    assert!(HexSpan::find("#1234", 0, 0).is_none());
+}
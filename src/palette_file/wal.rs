@@ -0,0 +1,90 @@
+use super::{HexSpan, SlotColors};
+use crate::terminal_palette::Slot;
+use regex::Regex;
+use std::io::{self};
+use std::path::Path;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads the `colors` and `special` maps of a pywal or wallust `colors.json`.
+/// The palette is named after the wallpaper.
+pub fn parse(content: &str) -> io::Result<SlotColors> {
+    static RE: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+        Regex::new(r#""(color\d{1,2}|foreground|background|cursor)"\s*:"#).unwrap()
+    });
+    let json: serde_json::Value =
+        serde_json::from_str(content).map_err(|err| invalid(err.to_string()))?;
+
+    let mut colors = SlotColors::new(content);
+    colors.name = json["wallpaper"]
+        .as_str()
+        .and_then(|wallpaper| Path::new(wallpaper).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned());
+    for (i, line) in content.lines().enumerate() {
+        for captures in RE.captures_iter(line) {
+            let Some(slot) = Slot::parse(&captures[1]) else {
+                continue;
+            };
+            let end = captures.get(0).unwrap().end();
+            if let Some((rgb, span)) = HexSpan::find(line, i, end) {
+                colors.push(slot, rgb, span);
+            }
+        }
+    }
+    if let Some(missing) = (0..16).find(|index| colors.get(Slot::Ansi(*index)).is_none()) {
+        return Err(invalid(format!("missing color{}", missing)));
+    }
+    colors.sort();
+    Ok(colors)
+}
+
+#[test]
+fn test_wal_round_trip() {
+    let mut content = String::from(
+        "{\n    \"wallpaper\": \"/home/me/walls/forest.jpg\",\n    \"alpha\": \"100\",\n\
+         \n    \"special\": {\n        \"background\": \"#1d2021\",\n        \
+         \"foreground\": \"#ebdbb2\",\n        \"cursor\": \"#ebdbb2\"\n    },\n    \"colors\": {\n",
+    );
+    for index in 0..16 {
+        let comma = if index < 15 { "," } else { "" };
+        content.push_str(&format!(
+            "        \"color{}\": \"#{:06x}\"{}\n",
+            index,
+            index * 0x0f0f0f,
+            comma
+        ));
+    }
+    content.push_str("    }\n}\n");
+
+    let mut colors = parse(&content).unwrap();
+    assert_eq!(colors.name.as_deref(), Some("forest"));
+    let palette = colors.to_palette("colors");
+    assert_eq!(palette.swatches.len(), 19);
+    assert_eq!(palette.swatches[16].label, "foreground");
+    assert_eq!(colors.serialize(), content);
+
+    colors.set(17, (0x28, 0x28, 0x28));
+    colors.set(1, (0xcc, 0x24, 0x1d));
+    let edited = colors.serialize();
+    assert!(edited.contains("\"background\": \"#282828\""));
+    assert!(edited.contains("\"color1\": \"#cc241d\","));
+    assert_eq!(
+        parse(&edited).unwrap().get(Slot::Ansi(1)),
+        Some((0xcc, 0x24, 0x1d))
+    );
+
+    let unterminated = content.trim_end();
+    assert_eq!(parse(unterminated).unwrap().serialize(), unterminated);
+    let crlf = content.replace('\n', "\r\n");
+    let mut colors = parse(&crlf).unwrap();
+    assert_eq!(colors.serialize(), crlf);
+    colors.set(1, (0xfb, 0x49, 0x34));
+    assert_eq!(
+        colors.serialize(),
+        crlf.replace("\"#0f0f0f\"", "\"#fb4934\"")
+    );
+    assert!(parse("{\"colors\": {\"color0\": \"#000000\"}}").is_err());
+    assert!(parse("not json").is_err());
+}
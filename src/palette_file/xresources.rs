@@ -0,0 +1,89 @@
+use super::{HexSpan, SlotColors};
+use crate::terminal_palette::Slot;
+use std::io::{self};
+
+/// Reads the `colorN`, `foreground`, `background` and `cursorColor` resources
+/// of any class, like `*.color1` or `URxvt*background`. Comments, preprocessor
+/// lines and values that are not hex colors are skipped.
+pub fn parse(content: &str) -> io::Result<SlotColors> {
+    let mut colors = SlotColors::new(content);
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('!') || trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((key, _)) = line.split_once(':') else {
+            continue;
+        };
+        let resource = key.trim().rsplit(['.', '*']).next().unwrap_or_default();
+        let slot = match resource {
+            "cursorColor" => Slot::Cursor,
+            "foreground" | "background" => Slot::parse(resource).unwrap(),
+            _ if resource.starts_with("color") => match Slot::parse(resource) {
+                Some(slot) => slot,
+                None => continue,
+            },
+            _ => continue,
+        };
+        if let Some((rgb, span)) = HexSpan::find(line, i, key.len() + 1) {
+            colors.push(slot, rgb, span);
+        }
+    }
+    if colors.colors.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no color resources found",
+        ));
+    }
+    colors.sort();
+    Ok(colors)
+}
+
+#[test]
+fn test_xresources_round_trip() {
+    let content = "\
+! Generated by hand
+#define bg #1d2021
+*.background: bg
+*.foreground:   #EBDBB2
+URxvt*background: #282828
+*color0:  #282828
+*.color1: #cc241d
+XTerm*color1: #cc241d
+*.cursorColor: #ebdbb2
+*.font: Iosevka
+";
+    let mut colors = parse(content).unwrap();
+    let palette = colors.to_palette("Xresources");
+    let labels: Vec<_> = palette.swatches.iter().map(|s| s.label.as_str()).collect();
+    assert_eq!(
+        labels,
+        [
+            "color0 (black)",
+            "color1 (red)",
+            "foreground",
+            "background",
+            "cursor"
+        ]
+    );
+    assert_eq!(colors.serialize(), content);
+
+    colors.set(1, (0xfb, 0x49, 0x34));
+    colors.set(2, (0xff, 0xff, 0xff));
+    let edited = colors.serialize();
+    assert!(edited.contains("*.color1: #fb4934\nXTerm*color1: #fb4934\n"));
+    assert!(edited.contains("*.foreground:   #FFFFFF\n"));
+    assert!(edited.contains("*.background: bg\n"));
+
+    assert!(parse("*.font: Iosevka\n").is_err());
+
+    let crlf = content.replace('\n', "\r\n");
+    let mut colors = parse(&crlf).unwrap();
+    assert_eq!(colors.serialize(), crlf);
+    colors.set(1, (0xfb, 0x49, 0x34));
+    assert!(
+        colors
+            .serialize()
+            .contains("*.color1: #fb4934\r\nXTerm*color1: #fb4934\r\n")
+    );
+}
@@ -0,0 +1,110 @@
+use crossterm::event::MouseEvent;
+
+// With SGR-Pixels mouse reporting (mode 1016) the terminal reports the mouse
+// in pixels instead of cells. Events are converted back to cells, keeping
+// which half of the cell was clicked since the pickers draw two pixels per
+// cell.
+
+/// Asks whether mode 1016 is supported (DECRQM) and the size of a cell in
+/// pixels (XTWINOPS 16)
+pub const PIXEL_MOUSE_QUERY: &str = "\x1b[?1016$p\x1b[16t";
+pub const ENABLE_PIXEL_MOUSE: &str = "\x1b[?1016h";
+pub const DISABLE_PIXEL_MOUSE: &str = "\x1b[?1016l";
+
+/// Size of a terminal cell in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl CellSize {
+    /// Cell size from the pixel size of the window, which some terminals
+    /// leave at 0
+    pub fn from_window() -> Option<CellSize> {
+        let size = crossterm::terminal::window_size().ok()?;
+        if size.columns == 0 || size.rows == 0 {
+            return None;
+        }
+        let cell = CellSize {
+            width: size.width / size.columns,
+            height: size.height / size.rows,
+        };
+        (cell.width > 0 && cell.height > 0).then_some(cell)
+    }
+}
+
+/// Whether the replies to `PIXEL_MOUSE_QUERY` say mode 1016 can be enabled,
+/// and the cell size if the terminal reported it
+pub fn parse_pixel_mouse_replies(input: &[u8]) -> (bool, Option<CellSize>) {
+    let input = String::from_utf8_lossy(input);
+    // CSI ? 1016 ; Ps $ y, 0 is unknown and 4 permanently reset
+    let supported = input
+        .split("\x1b[?1016;")
+        .skip(1)
+        .any(|reply| matches!(reply.split_once("$y"), Some(("1" | "2" | "3", _))));
+    // CSI 6 ; height ; width t
+    let cell_size = input.split("\x1b[6;").skip(1).find_map(|reply| {
+        let (height, width) = reply.split_once('t')?.0.split_once(';')?;
+        let cell = CellSize {
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+        };
+        (cell.width > 0 && cell.height > 0).then_some(cell)
+    });
+    (supported, cell_size)
+}
+
+/// Converts an event reported in pixels to cells. Returns whether it is in
+/// the lower half of its cell.
+pub fn to_cells(mut event: MouseEvent, cell: CellSize) -> (MouseEvent, bool) {
+    let lower_half = (event.row % cell.height) * 2 >= cell.height;
+    event.column /= cell.width;
+    event.row /= cell.height;
+    (event, lower_half)
+}
+
+/// Pixel row picked at cell `row` of a picker `height` cells tall when the
+/// mouse is reported in cells. The cells are spread over all the pixel rows
+/// so that the first and the last one can both be picked.
+pub fn cell_row_to_pixel(row: u32, height: u32) -> u32 {
+    if height < 2 {
+        return row * 2;
+    }
+    (row * (2 * height - 1) + (height - 1) / 2) / (height - 1)
+}
+
+#[test]
+fn test_pixel_mouse() {
+    use crossterm::event::{KeyModifiers, MouseButton, MouseEventKind};
+
+    let replies = b"\x1b[?1016;2$y\x1b[6;20;9t\x1b[?62;22c";
+    let cell = CellSize {
+        width: 9,
+        height: 20,
+    };
+    assert_eq!(parse_pixel_mouse_replies(replies), (true, Some(cell)));
+    assert_eq!(
+        parse_pixel_mouse_replies(b"\x1b[?1016;0$y\x1b[?62;22c"),
+        (false, None)
+    );
+
+    let event = MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: 95,
+        row: 215,
+        modifiers: KeyModifiers::NONE,
+    };
+    let (cells, lower_half) = to_cells(event, cell);
+    assert_eq!((cells.column, cells.row, lower_half), (10, 10, true));
+    let (cells, lower_half) = to_cells(MouseEvent { row: 209, ..event }, cell);
+    assert_eq!((cells.row, lower_half), (10, false));
+}
+
+#[test]
+fn test_cell_row_to_pixel() {
+    assert_eq!(cell_row_to_pixel(0, 30), 0);
+    assert_eq!(cell_row_to_pixel(15, 30), 31);
+    assert_eq!(cell_row_to_pixel(29, 30), 59);
+    assert!(cell_row_to_pixel(30, 30) > 59);
+}
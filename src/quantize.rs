@@ -0,0 +1,169 @@
+use image::RgbImage;
+use palette::{FromColor, Lab, Srgb};
+
+/// Pixels used for clustering, larger images are sampled with a stride
+const MAX_SAMPLES: usize = 1 << 16;
+const MAX_ITERATIONS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DominantColor {
+    pub rgb: (u8, u8, u8),
+    /// Share of the image pixels in this cluster, between 0 and 1
+    pub population: f32,
+}
+
+/// Small xorshift generator so results only depend on the seed.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // The state must not be zero
+        XorShift(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+fn distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+fn nearest(centers: &[[f32; 3]], point: &[f32; 3]) -> usize {
+    let mut best = 0;
+    for (i, center) in centers.iter().enumerate() {
+        if distance(center, point) < distance(&centers[best], point) {
+            best = i;
+        }
+    }
+    best
+}
+
+/// Extracts up to `count` dominant colors with k-means clustering in CIELAB,
+/// initialized with k-means++. The result is sorted by decreasing population
+/// and is the same for the same image and seed.
+pub fn dominant_colors(image: &RgbImage, count: usize, seed: u64) -> Vec<DominantColor> {
+    let pixel_count = image.pixels().len();
+    if count == 0 || pixel_count == 0 {
+        return Vec::new();
+    }
+    let stride = pixel_count.div_ceil(MAX_SAMPLES);
+    let points: Vec<[f32; 3]> = image
+        .pixels()
+        .step_by(stride)
+        .map(|pixel| {
+            let lab = Lab::from_color(Srgb::from(pixel.0).into_format::<f32>());
+            [lab.l, lab.a, lab.b]
+        })
+        .collect();
+
+    // k-means++: each new center is drawn with a probability proportional to
+    // its squared distance to the closest existing center.
+    let mut rng = XorShift::new(seed);
+    let mut centers = vec![points[rng.next_u64() as usize % points.len()]];
+    let mut distances: Vec<f32> = points.iter().map(|p| distance(p, &centers[0])).collect();
+    while centers.len() < count {
+        let total: f32 = distances.iter().sum();
+        if total <= 0.0 {
+            // Fewer distinct colors than requested
+            break;
+        }
+        let mut target = rng.next_f32() * total;
+        let mut chosen = points.len() - 1;
+        for (i, d) in distances.iter().enumerate() {
+            if target < *d {
+                chosen = i;
+                break;
+            }
+            target -= d;
+        }
+        centers.push(points[chosen]);
+        for (d, point) in distances.iter_mut().zip(&points) {
+            *d = d.min(distance(point, &points[chosen]));
+        }
+    }
+
+    let mut assignments = vec![usize::MAX; points.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (assignment, point) in assignments.iter_mut().zip(&points) {
+            let cluster = nearest(&centers, point);
+            if *assignment != cluster {
+                *assignment = cluster;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        let mut sums = vec![[0f64; 4]; centers.len()];
+        for (cluster, point) in assignments.iter().zip(&points) {
+            let sum = &mut sums[*cluster];
+            for (total, value) in sum.iter_mut().zip(point) {
+                *total += *value as f64;
+            }
+            sum[3] += 1.0;
+        }
+        for (center, sum) in centers.iter_mut().zip(&sums) {
+            if sum[3] > 0.0 {
+                *center = [
+                    (sum[0] / sum[3]) as f32,
+                    (sum[1] / sum[3]) as f32,
+                    (sum[2] / sum[3]) as f32,
+                ];
+            }
+        }
+    }
+
+    let mut sizes = vec![0usize; centers.len()];
+    for cluster in &assignments {
+        sizes[*cluster] += 1;
+    }
+    let mut colors: Vec<DominantColor> = centers
+        .iter()
+        .zip(&sizes)
+        .filter(|(_, size)| **size > 0)
+        .map(|(center, size)| {
+            let lab = Lab::new(center[0], center[1], center[2]);
+            let rgb = Srgb::from_color(lab).into_format::<u8>().into_components();
+            DominantColor {
+                rgb,
+                population: *size as f32 / points.len() as f32,
+            }
+        })
+        .collect();
+    colors.sort_by(|a, b| b.population.total_cmp(&a.population));
+    colors
+}
+
+#[test]
+fn test_dominant_colors() {
+    // 50% red, 30% green, 20% blue stripes
+    let image = RgbImage::from_fn(10, 4, |x, _| match x {
+        0..5 => image::Rgb([255, 0, 0]),
+        5..8 => image::Rgb([0, 255, 0]),
+        _ => image::Rgb([0, 0, 255]),
+    });
+    let colors = dominant_colors(&image, 3, 42);
+    let rgbs: Vec<_> = colors.iter().map(|c| c.rgb).collect();
+    assert_eq!(rgbs, vec![(255, 0, 0), (0, 255, 0), (0, 0, 255)]);
+    assert!((colors[0].population - 0.5).abs() < 1e-6);
+    assert!((colors[2].population - 0.2).abs() < 1e-6);
+
+    // Only three distinct colors exist
+    assert_eq!(dominant_colors(&image, 8, 42).len(), 3);
+
+    let gradient = RgbImage::from_fn(64, 64, |x, y| image::Rgb([x as u8 * 4, y as u8 * 4, 128]));
+    assert_eq!(
+        dominant_colors(&gradient, 5, 7),
+        dominant_colors(&gradient, 5, 7)
+    );
+}
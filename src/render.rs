@@ -0,0 +1,539 @@
+use std::io::{self, Write};
+
+// Components draw with escape sequences as if they wrote to the terminal.
+// They write into a `Screen` instead, a grid of cells that interprets the
+// sequences they use, and `Renderer::present` sends the terminal only the
+// cells that changed since the previous frame.
+
+/// True color, or an entry of the terminal's palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Rgb(u8, u8, u8),
+    Indexed(u8),
+}
+
+/// Colors and attributes of a cell, `None` colors are the terminal defaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub reverse: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            ch: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+impl Cell {
+    /// Whether the foreground color and boldness show, they don't on a space
+    fn has_foreground(&self) -> bool {
+        self.ch != ' ' || self.style.reverse
+    }
+
+    /// Whether both cells look the same on the terminal
+    fn looks_like(&self, other: &Cell) -> bool {
+        if self.has_foreground() || other.has_foreground() {
+            self == other
+        } else {
+            self.style.bg == other.style.bg
+        }
+    }
+}
+
+/// In-memory terminal: a grid of cells updated by the escape sequences
+/// written to it. Only the sequences the components emit are interpreted,
+/// others (OSC, private modes) are kept to be sent as is.
+#[derive(Debug, Clone)]
+pub struct Screen {
+    pub width: u16,
+    pub height: u16,
+    cells: Vec<Cell>,
+    pub cursor: (u16, u16),
+    pub cursor_visible: bool,
+    pen: Style,
+    /// Incomplete escape sequence or character at the end of the last write
+    pending: Vec<u8>,
+    passthrough: Vec<u8>,
+}
+
+impl Screen {
+    pub fn new(width: u16, height: u16) -> Screen {
+        Screen {
+            width,
+            height,
+            cells: vec![Cell::default(); width as usize * height as usize],
+            cursor: (0, 0),
+            cursor_visible: false,
+            pen: Style::default(),
+            pending: Vec::new(),
+            passthrough: Vec::new(),
+        }
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Option<&Cell> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(&self.cells[y as usize * self.width as usize + x as usize])
+    }
+
+    /// Characters of a row, without the trailing spaces
+    pub fn row_text(&self, y: u16) -> String {
+        let start = y as usize * self.width as usize;
+        let row = &self.cells[start..start + self.width as usize];
+        let text: String = row.iter().map(|cell| cell.ch).collect();
+        text.trim_end().to_string()
+    }
+
+    /// Characters of every row, one line each
+    pub fn text(&self) -> String {
+        (0..self.height).map(|y| self.row_text(y) + "\n").collect()
+    }
+
+    /// Styled cells, one line per run of cells with the same style:
+    /// `y x1-x2 fg bg` and `bold` or `reverse`. Default colors are `-`. The
+    /// foreground of blank cells does not show and is left out.
+    pub fn styles(&self) -> String {
+        let color = |color: Option<Color>| match color {
+            Some(Color::Rgb(r, g, b)) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            Some(Color::Indexed(index)) => index.to_string(),
+            None => "-".to_string(),
+        };
+        let mut lines = String::new();
+        for y in 0..self.height {
+            let start = y as usize * self.width as usize;
+            let row = &self.cells[start..start + self.width as usize];
+            let mut x = 0;
+            let visible = |cell: &Cell| match cell.ch {
+                ' ' if !cell.style.reverse => Style {
+                    fg: None,
+                    bold: false,
+                    ..cell.style
+                },
+                _ => cell.style,
+            };
+            for run in row.chunk_by(|a, b| visible(a) == visible(b)) {
+                let style = visible(&run[0]);
+                if style != Style::default() {
+                    lines += &format!(
+                        "{} {}-{} {} {}{}{}\n",
+                        y,
+                        x,
+                        x + run.len() - 1,
+                        color(style.fg),
+                        color(style.bg),
+                        if style.bold { " bold" } else { "" },
+                        if style.reverse { " reverse" } else { "" },
+                    );
+                }
+                x += run.len();
+            }
+        }
+        lines
+    }
+
+    fn put(&mut self, ch: char) {
+        let (x, y) = self.cursor;
+        if x < self.width && y < self.height {
+            let index = y as usize * self.width as usize + x as usize;
+            self.cells[index] = Cell {
+                ch,
+                style: self.pen,
+            };
+        }
+        self.cursor.0 = x.saturating_add(1).min(self.width);
+    }
+
+    /// Erases cells with the background of the pen, as terminals do
+    fn erase(&mut self, y: u16, columns: std::ops::Range<u16>) {
+        if y >= self.height {
+            return;
+        }
+        let row = y as usize * self.width as usize;
+        let blank = Cell {
+            ch: ' ',
+            style: self.pen,
+        };
+        for x in columns.start..columns.end.min(self.width) {
+            self.cells[row + x as usize] = blank;
+        }
+    }
+
+    fn move_cursor(&mut self, x: i32, y: i32) {
+        let max_x = self.width.saturating_sub(1) as i32;
+        let max_y = self.height.saturating_sub(1) as i32;
+        self.cursor = (x.clamp(0, max_x) as u16, y.clamp(0, max_y) as u16);
+    }
+
+    fn select_graphic_rendition(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            self.pen = Style::default();
+        }
+        let mut params = params.iter();
+        while let Some(param) = params.next() {
+            match param {
+                0 => self.pen = Style::default(),
+                1 => self.pen.bold = true,
+                22 => self.pen.bold = false,
+                7 => self.pen.reverse = true,
+                27 => self.pen.reverse = false,
+                39 => self.pen.fg = None,
+                49 => self.pen.bg = None,
+                38 | 48 => {
+                    let color = match params.next() {
+                        Some(2) => match (params.next(), params.next(), params.next()) {
+                            (Some(r), Some(g), Some(b)) => {
+                                Some(Color::Rgb(*r as u8, *g as u8, *b as u8))
+                            }
+                            _ => None,
+                        },
+                        Some(5) => params.next().map(|index| Color::Indexed(*index as u8)),
+                        _ => None,
+                    };
+                    if *param == 38 {
+                        self.pen.fg = color;
+                    } else {
+                        self.pen.bg = color;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Applies a CSI sequence, `sequence` going from the ESC to the final byte
+    fn control_sequence(&mut self, sequence: &[u8]) {
+        let final_byte = sequence[sequence.len() - 1];
+        let params = &sequence[2..sequence.len() - 1];
+        if params.first() == Some(&b'?') {
+            match (params, final_byte) {
+                (b"?25", b'h') => self.cursor_visible = true,
+                (b"?25", b'l') => self.cursor_visible = false,
+                _ => self.passthrough.extend_from_slice(sequence),
+            }
+            return;
+        }
+        let numbers: Vec<u16> = String::from_utf8_lossy(params)
+            .split(';')
+            .map(|param| param.parse().unwrap_or(0))
+            .collect();
+        // Missing and zero counts move by one
+        let count = numbers.first().copied().unwrap_or(0).max(1) as i32;
+        let (x, y) = (self.cursor.0 as i32, self.cursor.1 as i32);
+        match final_byte {
+            b'H' | b'f' => {
+                let row = numbers.first().copied().unwrap_or(1).max(1) as i32;
+                let column = numbers.get(1).copied().unwrap_or(1).max(1) as i32;
+                self.move_cursor(column - 1, row - 1);
+            }
+            b'A' => self.move_cursor(x, y - count),
+            b'B' => self.move_cursor(x, y + count),
+            b'C' => self.move_cursor(x + count, y),
+            b'D' => self.move_cursor(x - count, y),
+            b'G' => self.move_cursor(count - 1, y),
+            b'm' if params.is_empty() => self.select_graphic_rendition(&[]),
+            b'm' => self.select_graphic_rendition(&numbers),
+            b'K' => {
+                let (y, width) = (self.cursor.1, self.width);
+                match numbers.first() {
+                    Some(1) => self.erase(y, 0..self.cursor.0 + 1),
+                    Some(2) => self.erase(y, 0..width),
+                    _ => self.erase(y, self.cursor.0..width),
+                }
+            }
+            b'J' if numbers.first() == Some(&2) => {
+                for y in 0..self.height {
+                    self.erase(y, 0..self.width);
+                }
+            }
+            _ => self.passthrough.extend_from_slice(sequence),
+        }
+    }
+
+    /// Length of the escape sequence at the start of `input`, `None` if it is
+    /// not complete yet
+    fn escape_sequence_len(input: &[u8]) -> Option<usize> {
+        match input.get(1)? {
+            b'[' => input[2..]
+                .iter()
+                .position(|b| (0x40..=0x7e).contains(b))
+                .map(|end| end + 3),
+            // OSC, ended by BEL or ST
+            b']' => (2..input.len()).find_map(|i| match (input[i], input.get(i + 1)) {
+                (0x07, _) => Some(i + 1),
+                (0x1b, Some(b'\\')) => Some(i + 2),
+                _ => None,
+            }),
+            _ => Some(2),
+        }
+    }
+
+    fn parse(&mut self) {
+        let input = std::mem::take(&mut self.pending);
+        let mut i = 0;
+        while i < input.len() {
+            let rest = &input[i..];
+            if rest[0] == 0x1b {
+                let Some(len) = Self::escape_sequence_len(rest) else {
+                    break;
+                };
+                if rest[1] == b'[' {
+                    self.control_sequence(&rest[..len]);
+                } else {
+                    self.passthrough.extend_from_slice(&rest[..len]);
+                }
+                i += len;
+                continue;
+            }
+            match rest[0] {
+                b'\r' => self.cursor.0 = 0,
+                b'\n' => self.move_cursor(self.cursor.0 as i32, self.cursor.1 as i32 + 1),
+                byte if byte < 0x20 => {}
+                byte => {
+                    let len = match byte {
+                        0xf0.. => 4,
+                        0xe0.. => 3,
+                        0xc0.. => 2,
+                        _ => 1,
+                    };
+                    if rest.len() < len {
+                        break;
+                    }
+                    let text = String::from_utf8_lossy(&rest[..len]);
+                    for ch in text.chars() {
+                        self.put(ch);
+                    }
+                    i += len;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+        self.pending = input[i..].to_vec();
+    }
+}
+
+impl Write for Screen {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        self.parse();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Bytes drawn by the components for a frame and bytes sent to the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameStats {
+    pub drawn: usize,
+    pub emitted: usize,
+}
+
+/// Screen the components draw on, with what the terminal shows to diff it
+/// against
+pub struct Renderer {
+    back: Screen,
+    /// `None` when the content of the terminal is unknown
+    front: Option<Screen>,
+    /// Attributes the terminal is set to, `None` when unknown
+    pen: Option<Style>,
+    drawn: usize,
+}
+
+impl Renderer {
+    pub fn new(width: u16, height: u16) -> Renderer {
+        Renderer {
+            back: Screen::new(width, height),
+            front: None,
+            pen: None,
+            drawn: 0,
+        }
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.back
+    }
+
+    /// Starts over with an empty screen, the next frame is sent whole
+    pub fn resize(&mut self, width: u16, height: u16) {
+        let mut back = Screen::new(width, height);
+        back.pen = self.back.pen;
+        back.cursor_visible = self.back.cursor_visible;
+        self.back = back;
+        self.front = None;
+        self.pen = None;
+    }
+
+    /// Sends the cells that changed since the last frame, moving the cursor
+    /// only between cells that are not adjacent and setting only the
+    /// attributes that differ from the previous cell
+    pub fn present(&mut self, out: &mut impl Write) -> io::Result<FrameStats> {
+        let mut bytes = std::mem::take(&mut self.back.passthrough);
+        let back = &self.back;
+        let front = self.front.as_ref();
+        let mut cursor = front.map(|front| front.cursor);
+        let mut pen = self.pen;
+        for y in 0..back.height {
+            for x in 0..back.width {
+                let cell = back.cells[y as usize * back.width as usize + x as usize];
+                if front
+                    .and_then(|front| front.cell(x, y))
+                    .is_some_and(|old| old.looks_like(&cell))
+                {
+                    continue;
+                }
+                match cursor {
+                    Some((cx, cy)) if (cx, cy) == (x, y) => {}
+                    Some((cx, cy)) if cy == y && cx < x => {
+                        write!(bytes, "\x1b[{}C", x - cx)?;
+                    }
+                    _ => write!(bytes, "\x1b[{};{}H", y + 1, x + 1)?,
+                }
+                write_style(&mut bytes, pen, &cell)?;
+                pen = Some(match pen {
+                    // Only the background of spaces was set
+                    Some(pen) if !cell.has_foreground() => Style {
+                        bg: cell.style.bg,
+                        reverse: cell.style.reverse,
+                        ..pen
+                    },
+                    _ => cell.style,
+                });
+                write!(bytes, "{}", cell.ch)?;
+                // Terminals stay on the last column until the next character
+                cursor = (x + 1 < back.width).then_some((x + 1, y));
+            }
+        }
+        if cursor != Some(back.cursor) {
+            write!(bytes, "\x1b[{};{}H", back.cursor.1 + 1, back.cursor.0 + 1)?;
+        }
+        if front.is_none_or(|front| front.cursor_visible != back.cursor_visible) {
+            bytes.extend_from_slice(if back.cursor_visible {
+                b"\x1b[?25h"
+            } else {
+                b"\x1b[?25l"
+            });
+        }
+        if !bytes.is_empty() {
+            out.write_all(&bytes)?;
+            out.flush()?;
+        }
+        let mut front = self.back.clone();
+        front.pending.clear();
+        self.front = Some(front);
+        self.pen = pen;
+        let stats = FrameStats {
+            drawn: self.drawn,
+            emitted: bytes.len(),
+        };
+        self.drawn = 0;
+        Ok(stats)
+    }
+}
+
+/// Sets the attributes of `cell` that differ from `pen` in a single SGR
+fn write_style(bytes: &mut Vec<u8>, pen: Option<Style>, cell: &Cell) -> io::Result<()> {
+    let style = cell.style;
+    let mut params = Vec::new();
+    let pen = pen.unwrap_or_else(|| {
+        params.push("0".to_string());
+        Style::default()
+    });
+    let color = |code: u8, color: Option<Color>| match color {
+        Some(Color::Rgb(r, g, b)) => format!("{};2;{};{};{}", code, r, g, b),
+        Some(Color::Indexed(index)) => format!("{};5;{}", code, index),
+        None => format!("{}", code + 1),
+    };
+    if cell.has_foreground() {
+        if style.fg != pen.fg {
+            params.push(color(38, style.fg));
+        }
+        if style.bold != pen.bold {
+            params.push(if style.bold { "1" } else { "22" }.to_string());
+        }
+    }
+    if style.bg != pen.bg {
+        params.push(color(48, style.bg));
+    }
+    if style.reverse != pen.reverse {
+        params.push(if style.reverse { "7" } else { "27" }.to_string());
+    }
+    if !params.is_empty() {
+        write!(bytes, "\x1b[{}m", params.join(";"))?;
+    }
+    Ok(())
+}
+
+impl Write for Renderer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.drawn += buf.len();
+        self.back.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_renderer_diff() {
+    let mut renderer = Renderer::new(20, 4);
+    let mut terminal = Screen::new(20, 4);
+    write!(renderer, "\x1b[2;3H\x1b[1mHEX:\x1b[22m #ff0000\x1b[?25h").unwrap();
+    let stats = renderer.present(&mut terminal).unwrap();
+    assert_eq!(terminal.row_text(1), "  HEX: #ff0000");
+    assert!(terminal.cell(2, 1).unwrap().style.bold);
+    assert!(!terminal.cell(7, 1).unwrap().style.bold);
+    assert_eq!((terminal.cursor, terminal.cursor_visible), ((14, 1), true));
+    assert!(stats.emitted > 0);
+
+    // Sequences the grid does not model are sent as is, even when split
+    write!(renderer, "\x1b]4;1;rgb:ff/00/00").unwrap();
+    write!(renderer, "\x07").unwrap();
+    let mut bytes = Vec::new();
+    renderer.present(&mut bytes).unwrap();
+    assert_eq!(bytes, b"\x1b]4;1;rgb:ff/00/00\x07");
+
+    // Drawing the same thing again sends nothing
+    write!(renderer, "\x1b[2;3H\x1b[1mHEX:\x1b[22m #ff0000").unwrap();
+    assert_eq!(renderer.present(&mut terminal).unwrap().emitted, 0);
+
+    // Only the changed digits are sent, the attributes are already set
+    write!(renderer, "\x1b[2;3H\x1b[1mHEX:\x1b[22m #ff5733").unwrap();
+    let mut bytes = Vec::new();
+    renderer.present(&mut bytes).unwrap();
+    assert_eq!(bytes, b"\x1b[2;11H5733");
+    terminal.write_all(&bytes).unwrap();
+    assert_eq!(terminal.row_text(1), "  HEX: #ff5733");
+
+    // Colors set for a run of cells are sent once
+    write!(
+        renderer,
+        "\x1b[4;1H\x1b[38;2;1;2;3;48;2;4;5;6m\u{2584}\u{2584}"
+    )
+    .unwrap();
+    let mut bytes = Vec::new();
+    renderer.present(&mut bytes).unwrap();
+    assert_eq!(
+        String::from_utf8(bytes).unwrap(),
+        "\x1b[4;1H\x1b[38;2;1;2;3;48;2;4;5;6m\u{2584}\u{2584}"
+    );
+    let mut screen = Screen::new(20, 2);
+    write!(screen, "\x1b[1mHEX:\x1b[0m \x1b[2;3H\x1b[7;38;5;1m\u{2584}").unwrap();
+    assert_eq!(screen.styles(), "0 0-3 - - bold\n1 2-2 1 - reverse\n");
+}
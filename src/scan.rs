@@ -0,0 +1,95 @@
+use crate::color_literal::{ColorLiteral, scan};
+use crate::color_palette::ColorPalette;
+use crate::extract::OutputFormat;
+use crate::utils::rgb_from_hsv;
+use serde_json::json;
+
+/// Color literal found in a file or the standard input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanMatch {
+    pub source: String,
+    /// Line and column of the literal, from 1, the column counts characters
+    pub line: usize,
+    pub column: usize,
+    /// Byte offset of the literal in the whole text
+    pub offset: usize,
+    pub literal: ColorLiteral,
+}
+
+/// Every color literal of the text, line by line
+pub fn scan_text(source: &str, text: &str) -> Vec<ScanMatch> {
+    let mut matches = Vec::new();
+    let mut line_offset = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        for literal in scan(line) {
+            matches.push(ScanMatch {
+                source: source.to_string(),
+                line: i + 1,
+                column: line[..literal.span.start].chars().count() + 1,
+                offset: line_offset + literal.span.start,
+                literal,
+            });
+        }
+        line_offset += line.len();
+    }
+    matches
+}
+
+/// Palette of the literals labeled with their text, in order of appearance
+pub fn palette_from_matches(matches: &[ScanMatch], name: &str) -> ColorPalette {
+    let mut palette = ColorPalette::new(name);
+    for scan_match in matches {
+        palette.push(scan_match.literal.text.clone(), scan_match.literal.color);
+    }
+    palette
+}
+
+/// `source:line:column: literal` lines like grep, a JSON array or a GIMP palette
+pub fn format_matches(matches: &[ScanMatch], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => matches
+            .iter()
+            .map(|m| format!("{}:{}:{}: {}\n", m.source, m.line, m.column, m.literal.text))
+            .collect(),
+        OutputFormat::Json => {
+            let matches: Vec<_> = matches
+                .iter()
+                .map(|m| {
+                    let (r, g, b) = rgb_from_hsv(&m.literal.color);
+                    json!({
+                        "source": m.source,
+                        "line": m.line,
+                        "column": m.column,
+                        "start": m.offset,
+                        "end": m.offset + m.literal.text.len(),
+                        "format": m.literal.format.name(),
+                        "text": m.literal.text,
+                        "hex": format!("#{:02X}{:02X}{:02X}", r, g, b),
+                    })
+                })
+                .collect();
+            let mut out = serde_json::to_string_pretty(&matches).unwrap_or_default();
+            out.push('\n');
+            out
+        }
+        OutputFormat::Gpl => palette_from_matches(matches, "scan").to_gpl(),
+    }
+}
+
+#[test]
+fn test_scan_text() {
+    let text = ".a { color: #FF5733; }\n/* é */ .b { fill: rgb(0, 128, 255); stroke: 0x00ff00 }\n";
+    let matches = scan_text("style.css", text);
+    assert_eq!(matches.len(), 3);
+    assert_eq!((matches[1].line, matches[1].column), (2, 20));
+    assert_eq!(&text[matches[1].offset..][..16], "rgb(0, 128, 255)");
+    assert_eq!(
+        format_matches(&matches, OutputFormat::Text),
+        "style.css:1:13: #FF5733\nstyle.css:2:20: rgb(0, 128, 255)\nstyle.css:2:46: 0x00ff00\n"
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&format_matches(&matches, OutputFormat::Json)).unwrap();
+    assert_eq!(json[1]["format"], "rgb");
+    assert_eq!(json[1]["hex"], "#0080FF");
+    assert_eq!(json[2]["end"], json!(text.len() - 3));
+}
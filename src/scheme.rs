@@ -0,0 +1,383 @@
+use crate::terminal_palette::{Slot, TerminalPalette};
+use std::path::Path;
+
+/// Complete terminal color scheme, as written in terminal configurations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scheme {
+    pub name: String,
+    pub ansi: [(u8, u8, u8); 16],
+    pub foreground: (u8, u8, u8),
+    pub background: (u8, u8, u8),
+    pub cursor: (u8, u8, u8),
+}
+
+/// xterm colors, used for the slots the terminal did not report
+pub const DEFAULT_ANSI: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+const NORMAL_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// Windows Terminal calls magenta purple
+const WINDOWS_TERMINAL_NAMES: [&str; 8] = [
+    "Black", "Red", "Green", "Yellow", "Blue", "Purple", "Cyan", "White",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemeFormat {
+    Alacritty,
+    Kitty,
+    WezTerm,
+    Foot,
+    WindowsTerminal,
+    Xresources,
+    /// Shell script applying the scheme to the running terminal
+    Shell,
+}
+
+impl Scheme {
+    /// Missing ANSI colors fall back to the xterm ones, the foreground and
+    /// background to white and black and the cursor to the foreground.
+    pub fn from_terminal_palette(palette: &TerminalPalette, name: impl Into<String>) -> Scheme {
+        let mut ansi = DEFAULT_ANSI;
+        for (color, reported) in ansi.iter_mut().zip(palette.ansi) {
+            if let Some(reported) = reported {
+                *color = reported;
+            }
+        }
+        let foreground = palette.foreground.unwrap_or(ansi[7]);
+        Scheme {
+            name: name.into(),
+            ansi,
+            foreground,
+            background: palette.background.unwrap_or(ansi[0]),
+            cursor: palette.cursor.unwrap_or(foreground),
+        }
+    }
+
+    pub fn get(&self, slot: Slot) -> (u8, u8, u8) {
+        match slot {
+            Slot::Ansi(index) => self.ansi[index],
+            Slot::Foreground => self.foreground,
+            Slot::Background => self.background,
+            Slot::Cursor => self.cursor,
+        }
+    }
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// The name on a single comment line, a line break in it would start a
+/// configuration line
+fn comment(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+/// JSON strings are also valid TOML basic strings
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_default()
+}
+
+impl SchemeFormat {
+    pub const NAMES: &str = "alacritty, kitty, wezterm, foot, windows-terminal, xresources or sh";
+
+    pub fn parse(s: &str) -> Option<SchemeFormat> {
+        match s.to_ascii_lowercase().as_str() {
+            "alacritty" => Some(SchemeFormat::Alacritty),
+            "kitty" => Some(SchemeFormat::Kitty),
+            "wezterm" => Some(SchemeFormat::WezTerm),
+            "foot" => Some(SchemeFormat::Foot),
+            "windows-terminal" | "wt" => Some(SchemeFormat::WindowsTerminal),
+            "xresources" => Some(SchemeFormat::Xresources),
+            "sh" | "shell" => Some(SchemeFormat::Shell),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the file name, `.toml` files are Alacritty
+    /// configurations unless the path mentions WezTerm.
+    pub fn from_path(path: &Path) -> Option<SchemeFormat> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("sh") => Some(SchemeFormat::Shell),
+            Some("toml")
+                if path
+                    .to_string_lossy()
+                    .to_ascii_lowercase()
+                    .contains("wezterm") =>
+            {
+                Some(SchemeFormat::WezTerm)
+            }
+            Some("toml") => Some(SchemeFormat::Alacritty),
+            Some("conf") => Some(SchemeFormat::Kitty),
+            Some("ini") => Some(SchemeFormat::Foot),
+            Some("json") => Some(SchemeFormat::WindowsTerminal),
+            _ if name.contains("xresources") || name.contains("xdefaults") => {
+                Some(SchemeFormat::Xresources)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn write(&self, scheme: &Scheme) -> String {
+        match self {
+            SchemeFormat::Alacritty => write_alacritty(scheme),
+            SchemeFormat::Kitty => write_kitty(scheme),
+            SchemeFormat::WezTerm => write_wezterm(scheme),
+            SchemeFormat::Foot => write_foot(scheme),
+            SchemeFormat::WindowsTerminal => write_windows_terminal(scheme),
+            SchemeFormat::Xresources => write_xresources(scheme),
+            SchemeFormat::Shell => write_shell(scheme),
+        }
+    }
+}
+
+fn write_alacritty(scheme: &Scheme) -> String {
+    let mut out = format!("# {}\n\n[colors.primary]\n", comment(&scheme.name));
+    out.push_str(&format!("background = \"{}\"\n", hex(scheme.background)));
+    out.push_str(&format!("foreground = \"{}\"\n", hex(scheme.foreground)));
+    out.push_str("\n[colors.cursor]\n");
+    out.push_str(&format!("cursor = \"{}\"\n", hex(scheme.cursor)));
+    out.push_str(&format!("text = \"{}\"\n", hex(scheme.background)));
+    for (section, colors) in [("normal", &scheme.ansi[..8]), ("bright", &scheme.ansi[8..])] {
+        out.push_str(&format!("\n[colors.{}]\n", section));
+        for (name, color) in NORMAL_NAMES.iter().zip(colors) {
+            out.push_str(&format!("{} = \"{}\"\n", name, hex(*color)));
+        }
+    }
+    out
+}
+
+fn write_kitty(scheme: &Scheme) -> String {
+    let mut out = format!("# {}\n", comment(&scheme.name));
+    out.push_str(&format!("foreground {}\n", hex(scheme.foreground)));
+    out.push_str(&format!("background {}\n", hex(scheme.background)));
+    out.push_str(&format!("cursor {}\n", hex(scheme.cursor)));
+    out.push_str(&format!("cursor_text_color {}\n", hex(scheme.background)));
+    for (i, color) in scheme.ansi.iter().enumerate() {
+        out.push_str(&format!("color{} {}\n", i, hex(*color)));
+    }
+    out
+}
+
+fn write_wezterm(scheme: &Scheme) -> String {
+    let list = |colors: &[(u8, u8, u8)]| {
+        colors
+            .iter()
+            .map(|color| format!("\"{}\"", hex(*color)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut out = String::from("[colors]\n");
+    out.push_str(&format!("foreground = \"{}\"\n", hex(scheme.foreground)));
+    out.push_str(&format!("background = \"{}\"\n", hex(scheme.background)));
+    out.push_str(&format!("cursor_bg = \"{}\"\n", hex(scheme.cursor)));
+    out.push_str(&format!("cursor_border = \"{}\"\n", hex(scheme.cursor)));
+    out.push_str(&format!("cursor_fg = \"{}\"\n", hex(scheme.background)));
+    out.push_str(&format!("ansi = [{}]\n", list(&scheme.ansi[..8])));
+    out.push_str(&format!("brights = [{}]\n", list(&scheme.ansi[8..])));
+    out.push_str(&format!("\n[metadata]\nname = {}\n", quote(&scheme.name)));
+    out
+}
+
+/// foot takes the colors without the `#`
+fn write_foot(scheme: &Scheme) -> String {
+    let bare = |color| hex(color)[1..].to_string();
+    let mut out = format!("# {}\n\n[cursor]\n", comment(&scheme.name));
+    out.push_str(&format!(
+        "color={} {}\n",
+        bare(scheme.background),
+        bare(scheme.cursor)
+    ));
+    out.push_str("\n[colors]\n");
+    out.push_str(&format!("foreground={}\n", bare(scheme.foreground)));
+    out.push_str(&format!("background={}\n", bare(scheme.background)));
+    for (i, color) in scheme.ansi.iter().enumerate() {
+        let key = if i < 8 { "regular" } else { "bright" };
+        out.push_str(&format!("{}{}={}\n", key, i % 8, bare(*color)));
+    }
+    out
+}
+
+/// Scheme object for the `schemes` list of the Windows Terminal settings
+fn write_windows_terminal(scheme: &Scheme) -> String {
+    let mut fields = vec![
+        ("name".to_string(), quote(&scheme.name)),
+        ("foreground".to_string(), quote(&hex(scheme.foreground))),
+        ("background".to_string(), quote(&hex(scheme.background))),
+        ("cursorColor".to_string(), quote(&hex(scheme.cursor))),
+    ];
+    for (i, color) in scheme.ansi.iter().enumerate() {
+        let name = WINDOWS_TERMINAL_NAMES[i % 8];
+        let key = if i < 8 {
+            name.to_ascii_lowercase()
+        } else {
+            format!("bright{}", name)
+        };
+        fields.push((key, quote(&hex(*color))));
+    }
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|(key, value)| format!("    \"{}\": {}", key, value))
+        .collect();
+    format!("{{\n{}\n}}\n", fields.join(",\n"))
+}
+
+fn write_xresources(scheme: &Scheme) -> String {
+    let mut out = format!("! {}\n", comment(&scheme.name));
+    out.push_str(&format!("*.foreground: {}\n", hex(scheme.foreground)));
+    out.push_str(&format!("*.background: {}\n", hex(scheme.background)));
+    out.push_str(&format!("*.cursorColor: {}\n", hex(scheme.cursor)));
+    for (i, color) in scheme.ansi.iter().enumerate() {
+        out.push_str(&format!("*.color{}: {}\n", i, hex(*color)));
+    }
+    out
+}
+
+fn write_shell(scheme: &Scheme) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n# {}, generated by tui-color-picker\n",
+        comment(&scheme.name)
+    );
+    let slots = (0..16)
+        .map(Slot::Ansi)
+        .chain([Slot::Foreground, Slot::Background, Slot::Cursor]);
+    for slot in slots {
+        let sequence = slot
+            .set_sequence(scheme.get(slot))
+            .replace('\\', "\\\\")
+            .replace('\x1b', "\\033");
+        script.push_str(&format!("printf '{}'\n", sequence));
+    }
+    script
+}
+
+#[cfg(test)]
+fn golden_scheme() -> Scheme {
+    let mut palette = TerminalPalette::default();
+    palette.ansi[1] = Some((0xcc, 0x24, 0x1d));
+    palette.ansi[2] = Some((0x98, 0x97, 0x1a));
+    palette.ansi[12] = Some((0x83, 0xa5, 0x98));
+    palette.foreground = Some((0xeb, 0xdb, 0xb2));
+    palette.background = Some((0x28, 0x28, 0x28));
+    Scheme::from_terminal_palette(&palette, "Golden \"test\"")
+}
+
+#[cfg(test)]
+fn assert_golden(format: SchemeFormat, golden: &str) {
+    assert_eq!(format.write(&golden_scheme()), golden, "{:?}", format);
+}
+
+#[test]
+fn test_alacritty() {
+    assert_golden(
+        SchemeFormat::Alacritty,
+        include_str!("../tests/golden/scheme.alacritty.toml"),
+    );
+}
+
+#[test]
+fn test_kitty() {
+    assert_golden(
+        SchemeFormat::Kitty,
+        include_str!("../tests/golden/scheme.kitty.conf"),
+    );
+}
+
+#[test]
+fn test_wezterm() {
+    assert_golden(
+        SchemeFormat::WezTerm,
+        include_str!("../tests/golden/scheme.wezterm.toml"),
+    );
+}
+
+#[test]
+fn test_foot() {
+    assert_golden(
+        SchemeFormat::Foot,
+        include_str!("../tests/golden/scheme.foot.ini"),
+    );
+}
+
+#[test]
+fn test_windows_terminal() {
+    assert_golden(
+        SchemeFormat::WindowsTerminal,
+        include_str!("../tests/golden/scheme.windows-terminal.json"),
+    );
+}
+
+#[test]
+fn test_xresources() {
+    assert_golden(
+        SchemeFormat::Xresources,
+        include_str!("../tests/golden/scheme.Xresources"),
+    );
+}
+
+#[test]
+fn test_shell() {
+    assert_golden(
+        SchemeFormat::Shell,
+        include_str!("../tests/golden/scheme.sh"),
+    );
+}
+
+#[test]
+fn test_scheme_name_comment() {
+    let mut scheme = golden_scheme();
+    scheme.name = "evil\nforeground #000000\r\x1b]0;x".to_string();
+    for format in [
+        SchemeFormat::Alacritty,
+        SchemeFormat::Kitty,
+        SchemeFormat::Foot,
+        SchemeFormat::Xresources,
+        SchemeFormat::Shell,
+    ] {
+        let out = format.write(&scheme);
+        assert!(!out.contains("\nforeground #000000"), "{:?}", format);
+        assert!(!out.contains('\r') && !out.contains('\x1b'), "{:?}", format);
+    }
+}
+
+#[test]
+fn test_scheme_format_from_path() {
+    assert_eq!(
+        SchemeFormat::from_path(Path::new("~/.config/wezterm/colors/mine.toml")),
+        Some(SchemeFormat::WezTerm)
+    );
+    assert_eq!(
+        SchemeFormat::from_path(Path::new("alacritty.toml")),
+        Some(SchemeFormat::Alacritty)
+    );
+    assert_eq!(
+        SchemeFormat::from_path(Path::new(".Xresources")),
+        Some(SchemeFormat::Xresources)
+    );
+    assert_eq!(SchemeFormat::from_path(Path::new("theme")), None);
+}
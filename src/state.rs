@@ -1,23 +1,46 @@
-use crate::clipboard::{paste::*, ui::*};
+use crate::backend::Backend;
+use crate::clipboard::{paste::*, selection::Selection, ui::*};
+use crate::color_model::{keep_hue, with_rgb_channel};
+use crate::color_palette::ColorPalette;
+use crate::color_space::ColorSettings;
+use crate::commands::{Action, parse_command};
+use crate::edit_target::EditTarget;
+use crate::extract::palette_from_dominant_colors;
+use crate::history::History;
+#[cfg(unix)]
+use crate::ipc::{IpcServer, Request, color_json};
+use crate::palette_file::PaletteFile;
+use crate::pixel_mouse::{
+    CellSize, DISABLE_PIXEL_MOUSE, ENABLE_PIXEL_MOUSE, cell_row_to_pixel, to_cells,
+};
+use crate::quantize::dominant_colors;
+use crate::render::Renderer;
+use crate::scan::{palette_from_matches, scan_text};
+use crate::scheme::{Scheme, SchemeFormat};
+use crate::terminal_palette::{
+    RESET_PALETTE_SEQUENCE, Slot, TERMINAL_PALETTE_NAME, TerminalPalette,
+};
 use crate::types::*;
+use crate::ui::ansi_preview::draw_ansi_preview;
+use crate::ui::command_line::CommandLine;
 use crate::ui::hue_picker::HuePicker;
-use crate::ui::inputs::{Focus, Inputs};
+use crate::ui::image_view::ImageView;
+use crate::ui::inputs::{FieldEvent, Focus, Inputs};
 use crate::ui::saturation_value_picker::SaturationValuePicker;
+use crate::ui::status_bar::{MessageLevel, StatusBar};
+use crate::ui::swatch_strip::SwatchStrip;
 use crate::ui::value_display::draw_value_display;
 use crate::utils::*;
-use std::io::{self, Write, stdout};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use crate::constants::*;
 use crate::crossterm_commands::*;
 use crossterm::cursor::MoveTo;
 use crossterm::style::Print;
-use crossterm::{
-    cursor::{Hide, Show},
-    event::*,
-    execute,
-    style::{Color, SetBackgroundColor},
-    terminal::*,
-};
+use crossterm::{event::*, execute, queue, terminal::*};
+use palette::Hsv;
 use palette::RgbHue;
 use palette::SetHue;
 
@@ -25,9 +48,45 @@ pub struct State {
     pub sv_picker: SaturationValuePicker,
     pub hue_picker: HuePicker,
     pub inputs: Inputs,
+    pub status_bar: StatusBar,
+    pub history_strip: SwatchStrip,
+    pub history: History,
+    pub palette_strip: SwatchStrip,
+    pub palette: ColorPalette,
+    /// Theme file the palette was imported from
+    pub palette_file: Option<PaletteFile>,
+    /// Swatch of the imported palette following the selected color
+    pub editing_swatch: Option<usize>,
+    pub terminal_palette: TerminalPalette,
+    /// Terminal palette slot following the selected color
+    pub editing_slot: Option<Slot>,
+    pub image_view: ImageView,
+    pub command_line: CommandLine,
+    pub view: View,
     pub offset: Vec2,
     pub term_too_small: bool,
     pub flags: u8,
+    /// Default selection for copy and paste, set with `--selection`
+    pub selection: Selection,
+    /// White point, working space and gamut mapping of the color values
+    pub color_settings: ColorSettings,
+    /// Size of the cells when the mouse is reported in pixels
+    pub cell_size: Option<CellSize>,
+    /// Color before the focused field was edited, restored by Escape
+    pub pre_edit_color: Hsv,
+    /// Target and time of the last wheel step
+    wheel_burst: Option<(WheelTarget, Instant)>,
+    /// Color literal opened with `--edit`, rewritten on Enter
+    pub edit_target: Option<EditTarget>,
+    /// Server started with `--listen`
+    #[cfg(unix)]
+    pub ipc: Option<IpcServer>,
+    /// Last color sent to the IPC subscribers
+    #[cfg(unix)]
+    ipc_color: Option<(u8, u8, u8)>,
+    /// Screen the components draw on, sent to the backend by `present`
+    pub renderer: Renderer,
+    backend: Box<dyn Backend>,
 }
 
 pub enum Component {
@@ -35,67 +94,568 @@ pub enum Component {
     HuePicker,
     Inputs,
     ValueDisplay,
+    AnsiPreview,
+    StatusBar,
+    HistoryStrip,
+    PaletteStrip,
+    ImageView,
+    CommandLine,
+}
+
+/// What the wheel adjusts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WheelTarget {
+    Hue,
+    SVPicker,
+    Field(Focus),
+}
+
+/// What takes the main area of the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Picker,
+    Image,
 }
 
 pub const EXIT_FLAG: u8 = 1 << 0;
 pub const COPY_FLAG: u8 = 1 << 1;
-pub const COPY_CONFIRMED_FLAG: u8 = 1 << 2;
-pub const PASTE_CONFIRMED_FLAG: u8 = 1 << 3;
+pub const PALETTE_EDITED_FLAG: u8 = 1 << 2;
 
 impl Drop for State {
     /// Cleans up the terminal state when the application exits.
     fn drop(&mut self) {
-        let _ = execute!(stdout(), DisableMouseCapture, LeaveAlternateScreen, Show);
-        let _ = stdout().flush();
-        let _ = disable_raw_mode();
+        #[cfg(unix)]
+        {
+            let color = self.srgb_color();
+            if let Some(server) = &mut self.ipc {
+                server.broadcast("close", color_json(&color));
+            }
+        }
+        if self.flags & PALETTE_EDITED_FLAG != 0 {
+            let _ = execute!(self.backend, Print(RESET_PALETTE_SEQUENCE));
+        }
+        if self.cell_size.is_some() {
+            let _ = execute!(self.backend, Print(DISABLE_PIXEL_MOUSE));
+        }
+        let _ = self.backend.leave();
     }
 }
 
 impl State {
-    /// Only use this method to create a new State instance.
+    /// Only use this method to create a new State instance. The backend is
+    /// entered right away and left when the state is dropped.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         sv_picker: SaturationValuePicker,
         hue_picker: HuePicker,
         inputs: Inputs,
+        status_bar: StatusBar,
+        history_strip: SwatchStrip,
         terminal_width: u16,
         terminal_height: u16,
+        mut backend: Box<dyn Backend>,
     ) -> io::Result<Self> {
-        execute!(
-            stdout(),
-            Hide,
-            EnterAlternateScreen,
-            EnableMouseCapture,
-            SetBackgroundColor(Color::Rgb {
-                r: BACKGROUND_COLOR.r,
-                g: BACKGROUND_COLOR.g,
-                b: BACKGROUND_COLOR.b
-            }),
-            Clear(ClearType::All)
-        )?;
-        stdout().flush()?;
-        enable_raw_mode()?;
+        backend.enter()?;
+        let mut renderer = Renderer::new(terminal_width, terminal_height);
+        queue!(renderer, ResetDefaultColors(false), Clear(ClearType::All))?;
         Ok(Self {
             sv_picker,
             hue_picker,
             inputs,
+            status_bar,
+            history_strip,
+            history: History::new(None, HISTORY_LEN),
+            palette_strip: SwatchStrip::new(
+                PALETTE_STRIP_REL_POS,
+                "Palette:",
+                PALETTE_SWATCH_WIDTH,
+                PALETTE_COLUMNS,
+                PALETTE_CAPACITY,
+                false,
+            ),
+            palette: ColorPalette::default(),
+            palette_file: None,
+            editing_swatch: None,
+            terminal_palette: TerminalPalette::default(),
+            editing_slot: None,
+            image_view: ImageView::new(IMAGE_VIEW_REL_POS, TOTAL_WIDTH, IMAGE_VIEW_HEIGHT),
+            command_line: CommandLine::new(STATUS_BAR_REL_POS, TOTAL_WIDTH),
+            view: View::Picker,
             term_too_small: check_terminal_size(terminal_width, terminal_height),
             flags: 0,
             offset: Vec2::zero(),
+            selection: Selection::Clipboard,
+            color_settings: ColorSettings::default(),
+            cell_size: None,
+            pre_edit_color: Hsv::new(0.0, 0.0, 0.0),
+            wheel_burst: None,
+            edit_target: None,
+            #[cfg(unix)]
+            ipc: None,
+            #[cfg(unix)]
+            ipc_color: None,
+            renderer,
+            backend,
         })
     }
 
+    /// Sends what was drawn since the last frame to the backend
+    pub fn present(&mut self) -> io::Result<()> {
+        self.renderer.present(&mut self.backend)?;
+        Ok(())
+    }
+
     pub fn draw(&mut self, fade: bool) -> io::Result<()> {
-        self.sv_picker.draw(fade)?;
-        self.hue_picker.draw(fade)?;
         draw_value_display(
+            &mut self.renderer,
             &(VALUE_DISPLAY_REL_POS + self.offset),
             &self.sv_picker.selected_color,
+            &self.color_settings,
+            fade,
+        )?;
+        draw_ansi_preview(
+            &mut self.renderer,
+            &(ANSI_PREVIEW_REL_POS + self.offset),
+            self.editing_slot,
             fade,
         )?;
-        self.inputs.draw(&self.sv_picker.selected_color, fade)?;
+        match self.view {
+            View::Picker => {
+                self.sv_picker
+                    .draw(&mut self.renderer, &self.color_settings, fade)?;
+                self.hue_picker
+                    .draw(&mut self.renderer, &self.color_settings, fade)?;
+                self.inputs.draw(
+                    &mut self.renderer,
+                    &self.sv_picker.selected_color,
+                    &self.color_settings,
+                    fade,
+                )?;
+                self.history_strip
+                    .draw(&mut self.renderer, &self.history.colors, fade)?;
+                self.palette_strip
+                    .draw(&mut self.renderer, &self.palette.colors(), fade)?;
+            }
+            View::Image => self.image_view.draw(&mut self.renderer, fade)?,
+        }
+        if self.command_line.active {
+            self.command_line.draw(&mut self.renderer, fade)?;
+        } else {
+            self.status_bar.draw(&mut self.renderer, fade)?;
+        }
+        Ok(())
+    }
+
+    /// The selected color in sRGB, for everything outside of the picker
+    pub fn srgb_color(&self) -> Hsv {
+        self.color_settings.srgb(&self.sv_picker.selected_color)
+    }
+
+    /// Selects an sRGB color, converting it to the working space. Grays keep
+    /// the current hue.
+    pub fn set_srgb_color(&mut self, color: &Hsv) {
+        self.leave_field();
+        let previous = &self.sv_picker.selected_color;
+        self.sv_picker.selected_color = keep_hue(self.color_settings.from_srgb(color), previous);
+    }
+
+    /// Selects a color already in the working space, grays keep the current hue
+    fn set_color(&mut self, color: Hsv) {
+        self.leave_field();
+        self.sv_picker.selected_color = keep_hue(color, &self.sv_picker.selected_color);
+    }
+
+    /// Unfocuses the input field when the color changes from elsewhere, its
+    /// text and the color it was typed over would be stale
+    fn leave_field(&mut self) {
+        if self.inputs.focus != Focus::NONE {
+            let _ = self.inputs.lose_focus(&mut self.renderer);
+        }
+    }
+
+    pub fn set_view(&mut self, view: View) -> io::Result<()> {
+        let _ = self.inputs.lose_focus(&mut self.renderer);
+        self.view = view;
+        queue!(
+            self.renderer,
+            ResetDefaultColors(false),
+            Clear(ClearType::All)
+        )?;
+        self.draw(false)
+    }
+
+    pub fn open_image(&mut self, path: &Path) -> io::Result<()> {
+        match self.image_view.load(path) {
+            Ok(()) => {
+                self.set_view(View::Image)?;
+                self.notify(MessageLevel::Success, format!("Opened {}", path.display()))
+            }
+            Err(err) => self.notify(
+                MessageLevel::Error,
+                format!("Could not open {}: {}", path.display(), err),
+            ),
+        }
+    }
+
+    /// Shows the palette in the palette strip, switching to the picker.
+    pub fn load_palette(&mut self, palette: ColorPalette) -> io::Result<()> {
+        self.palette_file = None;
+        self.editing_swatch = None;
+        self.palette_strip.title = format!("Palette: {}", palette.name);
+        self.palette = palette;
+        if self.view == View::Picker {
+            self.draw_component(Component::PaletteStrip, false)
+        } else {
+            self.set_view(View::Picker)
+        }
+    }
+
+    pub fn select_palette_swatch(&mut self, index: usize) -> io::Result<()> {
+        let Some(swatch) = self.palette.swatches.get(index) else {
+            return Ok(());
+        };
+        let color = swatch.color;
+        let message = if self.palette_file.is_some() {
+            self.editing_swatch = Some(index);
+            format!("Editing {}, :write to save", swatch.label)
+        } else {
+            format!("Loaded {}", swatch.label)
+        };
+        self.set_srgb_color(&color);
+        self.draw(false)?;
+        self.notify(MessageLevel::Info, message)
+    }
+
+    /// Loads a theme file in the palette strip, its swatches are edited by
+    /// clicking them.
+    pub fn import_palette(&mut self, path: &Path) -> io::Result<()> {
+        let file = match PaletteFile::load(path) {
+            Ok(file) => file,
+            Err(err) => {
+                return self.notify(
+                    MessageLevel::Error,
+                    format!("Could not import {}: {}", path.display(), err),
+                );
+            }
+        };
+        let palette = file.to_palette();
+        let message = format!("Imported {}, click a swatch to edit it", palette.name);
+        self.load_palette(palette)?;
+        self.palette_file = Some(file);
+        self.notify(MessageLevel::Success, message)
+    }
+
+    /// Writes the imported palette back to its file, or to `path` which then
+    /// becomes its file.
+    pub fn write_palette(&mut self, path: Option<PathBuf>) -> io::Result<()> {
+        let Some(file) = &mut self.palette_file else {
+            return self.notify(
+                MessageLevel::Error,
+                "No imported palette, use :import <path>",
+            );
+        };
+        if let Some(path) = path {
+            file.path = path;
+        }
+        let result = write_atomic(&file.path, file.serialize().as_bytes());
+        let path = file.path.display().to_string();
+        match result {
+            Ok(()) => self.notify(MessageLevel::Success, format!("Wrote {}", path)),
+            Err(err) => self.notify(
+                MessageLevel::Error,
+                format!("Could not write {}: {}", path, err),
+            ),
+        }
+    }
+
+    pub fn extract_palette(&mut self, count: usize) -> io::Result<()> {
+        let Some(image) = &self.image_view.image else {
+            return self.notify(MessageLevel::Error, "No image opened, use :open <path>");
+        };
+        let colors = dominant_colors(image, count, 0);
+        let name = self
+            .image_view
+            .path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.load_palette(palette_from_dominant_colors(&colors, &name))?;
+        self.notify(
+            MessageLevel::Success,
+            format!("Extracted {} colors from {}", colors.len(), name),
+        )
+    }
+
+    pub fn run_command(&mut self, line: &str) -> io::Result<()> {
+        match parse_command(line) {
+            Ok(Action::Open(path)) => self.open_image(&path),
+            Ok(Action::Extract(count)) => {
+                self.extract_palette(count.unwrap_or(EXTRACT_DEFAULT_COUNT))
+            }
+            Ok(Action::Terminal) => {
+                if self.terminal_palette.is_empty() {
+                    return self.notify(
+                        MessageLevel::Error,
+                        "The terminal did not report its palette",
+                    );
+                }
+                self.load_palette(self.terminal_palette.to_palette())
+            }
+            Ok(Action::Import(path)) => self.import_palette(&path),
+            Ok(Action::Write(path)) => self.write_palette(path),
+            Ok(Action::EditSlot(slot)) => self.edit_terminal_slot(slot),
+            Ok(Action::Export(format, path)) => self.export_terminal_palette(format, &path),
+            Ok(Action::WhitePoint(white_point)) => {
+                let settings = &mut self.color_settings;
+                settings.white_point = white_point.unwrap_or(settings.white_point.toggled());
+                let message = format!("White point: {}", settings.white_point.name());
+                self.draw(false)?;
+                self.notify(MessageLevel::Info, message)
+            }
+            Ok(Action::Space(space)) => {
+                let settings = &mut self.color_settings;
+                let space = space.unwrap_or(settings.space.next());
+                // Keep the same color, gamut mapped if the space is narrower
+                let color = &mut self.sv_picker.selected_color;
+                *color = keep_hue(settings.convert(color, settings.space, space), color);
+                settings.space = space;
+                self.draw(false)?;
+                self.notify(
+                    MessageLevel::Info,
+                    format!("Working space: {}", space.name()),
+                )
+            }
+            Ok(Action::GamutMap(mapping)) => {
+                let settings = &mut self.color_settings;
+                settings.gamut_mapping = mapping.unwrap_or(settings.gamut_mapping.toggled());
+                let message = format!("Gamut mapping: {}", settings.gamut_mapping.name());
+                self.draw(false)?;
+                self.notify(MessageLevel::Info, message)
+            }
+            Ok(Action::Precision(precision)) => {
+                self.color_settings.precision = precision;
+                let message = match precision {
+                    Some(precision) => format!("Copying with {} decimals", precision),
+                    None => "Copying with the default decimals".to_string(),
+                };
+                self.notify(MessageLevel::Info, message)
+            }
+            Ok(Action::Zoom(factor)) => self.zoom(factor),
+            Ok(Action::Step(step, big_step)) => {
+                self.inputs.step = step;
+                if let Some(big_step) = big_step {
+                    self.inputs.big_step = big_step;
+                }
+                let message = format!(
+                    "Steps: {} with Up/Down, {} with PageUp/PageDown",
+                    self.inputs.step, self.inputs.big_step
+                );
+                self.notify(MessageLevel::Info, message)
+            }
+            Ok(Action::Quit) => {
+                self.flags |= EXIT_FLAG;
+                Ok(())
+            }
+            Err(message) => self.notify(MessageLevel::Error, message),
+        }
+    }
+
+    /// Queues a message in the status bar and shows it right away if nothing
+    /// else is displayed.
+    pub fn notify(&mut self, level: MessageLevel, text: impl Into<String>) -> io::Result<()> {
+        self.status_bar.push(level, text);
+        self.tick()
+    }
+
+    /// Runs on every iteration of the poll loop to expire timed UI elements
+    /// and propagate color changes.
+    pub fn tick(&mut self) -> io::Result<()> {
+        self.sync_terminal_slot()?;
+        self.sync_palette_swatch()?;
+        #[cfg(unix)]
+        self.poll_ipc()?;
+        if self.status_bar.tick(Instant::now()) && !self.term_too_small && !self.command_line.active
+        {
+            self.draw_component(Component::StatusBar, false)?;
+            if self.inputs.focus != Focus::NONE {
+                // Put the cursor back in the focused field
+                self.inputs.draw_input_str(&mut self.renderer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the requests of the IPC clients and sends the selected color
+    /// to the subscribers when it changed, whatever changed it.
+    #[cfg(unix)]
+    fn poll_ipc(&mut self) -> io::Result<()> {
+        let Some(server) = &mut self.ipc else {
+            return Ok(());
+        };
+        for (id, request, request_id) in server.poll() {
+            let request = match request {
+                Ok(request) => request,
+                Err(error) => {
+                    if let Some(server) = &mut self.ipc {
+                        server.reply(id, &request_id, Err(error));
+                    }
+                    continue;
+                }
+            };
+            match request {
+                Request::Set(color) => {
+                    self.set_srgb_color(&color);
+                    if !self.term_too_small {
+                        self.draw(false)?;
+                    }
+                }
+                Request::Get => {}
+                Request::Subscribe => {
+                    if let Some(server) = &mut self.ipc {
+                        server.subscribe(id);
+                    }
+                }
+                Request::Close => self.flags |= EXIT_FLAG,
+            }
+            let reply = color_json(&self.srgb_color());
+            if let Some(server) = &mut self.ipc {
+                server.reply(id, &request_id, Ok(reply));
+            }
+        }
+        let color = self.srgb_color();
+        let rgb = rgb_from_hsv(&color);
+        if self.ipc_color != Some(rgb)
+            && let Some(server) = &mut self.ipc
+        {
+            self.ipc_color = Some(rgb);
+            server.broadcast("change", color_json(&color));
+        }
         Ok(())
     }
 
+    /// Sends the selected color to the terminal palette slot being edited.
+    /// Runs from `tick` so every way of changing the color is covered.
+    fn sync_terminal_slot(&mut self) -> io::Result<()> {
+        let Some(slot) = self.editing_slot else {
+            return Ok(());
+        };
+        let rgb = rgb_from_hsv(&self.srgb_color());
+        // Slots the terminal did not report are left as they are
+        match self.terminal_palette.get(slot) {
+            None => return Ok(()),
+            Some(current) if current == rgb => return Ok(()),
+            Some(_) => {}
+        }
+        self.terminal_palette.set(slot, rgb);
+        self.flags |= PALETTE_EDITED_FLAG;
+        execute!(self.backend, Print(slot.set_sequence(rgb)))?;
+        if self.palette.name == TERMINAL_PALETTE_NAME {
+            self.palette = self.terminal_palette.to_palette();
+            if self.view == View::Picker && !self.term_too_small {
+                self.draw_component(Component::PaletteStrip, false)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the selected color to the imported palette swatch being edited
+    fn sync_palette_swatch(&mut self) -> io::Result<()> {
+        let color = self.srgb_color();
+        let (Some(index), Some(file)) = (self.editing_swatch, &mut self.palette_file) else {
+            return Ok(());
+        };
+        let Some(swatch) = self.palette.swatches.get_mut(index) else {
+            return Ok(());
+        };
+        let rgb = rgb_from_hsv(&color);
+        if rgb_from_hsv(&swatch.color) == rgb {
+            return Ok(());
+        }
+        swatch.color = color;
+        file.set(index, rgb);
+        if self.view == View::Picker && !self.term_too_small {
+            self.draw_component(Component::PaletteStrip, false)?;
+        }
+        Ok(())
+    }
+
+    pub fn edit_terminal_slot(&mut self, slot: Option<Slot>) -> io::Result<()> {
+        let Some(slot) = slot else {
+            self.editing_slot = None;
+            self.draw_component(Component::AnsiPreview, false)?;
+            return self.notify(MessageLevel::Info, "Stopped editing the terminal palette");
+        };
+        let Some((r, g, b)) = self.terminal_palette.get(slot) else {
+            return self.notify(
+                MessageLevel::Error,
+                format!("The terminal did not report {}", slot.name()),
+            );
+        };
+        self.editing_slot = Some(slot);
+        self.set_srgb_color(&hsv_from_rgb(r, g, b));
+        self.palette = self.terminal_palette.to_palette();
+        self.palette_file = None;
+        self.editing_swatch = None;
+        self.palette_strip.title = format!("Palette: {}", TERMINAL_PALETTE_NAME);
+        self.draw(false)?;
+        self.notify(
+            MessageLevel::Info,
+            format!("Editing {}, :slot off to stop", slot.name()),
+        )
+    }
+
+    /// Writes the terminal palette, named after the file, as a color scheme
+    pub fn export_terminal_palette(&mut self, format: SchemeFormat, path: &Path) -> io::Result<()> {
+        if self.terminal_palette.is_empty() {
+            return self.notify(MessageLevel::Error, "No terminal palette to export");
+        }
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or(TERMINAL_PALETTE_NAME.to_string());
+        let scheme = Scheme::from_terminal_palette(&self.terminal_palette, name);
+        match write_atomic(path, format.write(&scheme).as_bytes()) {
+            Ok(()) => self.notify(
+                MessageLevel::Success,
+                format!("Exported terminal palette to {}", path.display()),
+            ),
+            Err(err) => self.notify(
+                MessageLevel::Error,
+                format!("Could not write {}: {}", path.display(), err),
+            ),
+        }
+    }
+
+    /// Starts editing the literal with its color selected
+    pub fn open_edit_target(&mut self, target: EditTarget) -> io::Result<()> {
+        self.set_srgb_color(&target.literal.color);
+        let message = format!(
+            "Editing {} in {}:{}, Enter to save",
+            target.literal.text,
+            target.path.display(),
+            target.line
+        );
+        self.edit_target = Some(target);
+        self.draw(false)?;
+        self.notify(MessageLevel::Info, message)
+    }
+
+    /// Writes the selected color to the edited literal and exits
+    pub fn confirm_edit(&mut self) -> io::Result<()> {
+        let color = self.srgb_color();
+        let Some(target) = &mut self.edit_target else {
+            return Ok(());
+        };
+        match target.write(&color) {
+            Ok(_) => {
+                self.flags |= EXIT_FLAG;
+                Ok(())
+            }
+            Err(err) => self.notify(MessageLevel::Error, format!("Could not save: {}", err)),
+        }
+    }
+
     pub fn update_offset(&mut self, term_width: u16, term_height: u16) {
         let offset_x = (term_width as i16 - TOTAL_WIDTH as i16) / 2;
         let offset_y = (term_height as i16 - TOTAL_HEIGHT as i16) / 2;
@@ -109,27 +669,91 @@ impl State {
         self.inputs.pos = INPUTS_REL_POS + self.offset;
         self.sv_picker.pos = SV_PICKER_REL_POS + self.offset;
         self.hue_picker.pos = HUE_PICKER_REL_POS + self.offset;
+        self.status_bar.pos = STATUS_BAR_REL_POS + self.offset;
+        self.history_strip.pos = HISTORY_STRIP_REL_POS + self.offset;
+        self.palette_strip.pos = PALETTE_STRIP_REL_POS + self.offset;
+        self.image_view.pos = IMAGE_VIEW_REL_POS + self.offset;
+        self.command_line.pos = STATUS_BAR_REL_POS + self.offset;
     }
 
     pub fn draw_component(&mut self, component: Component, fade: bool) -> io::Result<()> {
         match component {
-            Component::SVPicker => self.sv_picker.draw(fade),
-            Component::HuePicker => self.hue_picker.draw(fade),
-            Component::Inputs => self.inputs.draw(&self.sv_picker.selected_color, fade),
+            Component::SVPicker => {
+                self.sv_picker
+                    .draw(&mut self.renderer, &self.color_settings, fade)
+            }
+            Component::HuePicker => {
+                self.hue_picker
+                    .draw(&mut self.renderer, &self.color_settings, fade)
+            }
+            Component::Inputs => self.inputs.draw(
+                &mut self.renderer,
+                &self.sv_picker.selected_color,
+                &self.color_settings,
+                fade,
+            ),
             Component::ValueDisplay => draw_value_display(
+                &mut self.renderer,
                 &(VALUE_DISPLAY_REL_POS + self.offset),
                 &self.sv_picker.selected_color,
+                &self.color_settings,
                 fade,
             ),
+            Component::AnsiPreview => draw_ansi_preview(
+                &mut self.renderer,
+                &(ANSI_PREVIEW_REL_POS + self.offset),
+                self.editing_slot,
+                fade,
+            ),
+            Component::StatusBar => self.status_bar.draw(&mut self.renderer, fade),
+            Component::HistoryStrip => {
+                self.history_strip
+                    .draw(&mut self.renderer, &self.history.colors, fade)
+            }
+            Component::PaletteStrip => {
+                self.palette_strip
+                    .draw(&mut self.renderer, &self.palette.colors(), fade)
+            }
+            Component::ImageView => self.image_view.draw(&mut self.renderer, fade),
+            Component::CommandLine => self.command_line.draw(&mut self.renderer, fade),
         }
     }
 
+    /// Adds the selected color to the recent colors and saves them.
+    pub fn record_history(&mut self) -> io::Result<()> {
+        if !self.history.push(self.srgb_color()) {
+            return Ok(());
+        }
+        self.draw_component(Component::HistoryStrip, false)?;
+        if let Err(err) = self.history.save() {
+            self.notify(
+                MessageLevel::Error,
+                format!("Could not save history: {}", err),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn restore_history(&mut self, index: usize) -> io::Result<()> {
+        let Some(&color) = self.history.colors.get(index) else {
+            return Ok(());
+        };
+        self.set_srgb_color(&color);
+        self.draw(false)?;
+        let (r, g, b) = rgb_from_hsv(&color);
+        self.notify(
+            MessageLevel::Info,
+            format!("Restored #{:02X}{:02X}{:02X}", r, g, b),
+        )
+    }
+
     pub fn handle_resize(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.renderer.resize(x, y);
         if check_terminal_size(x, y) {
             self.term_too_small = true;
             let warning_text = "Terminal too small!";
-            execute!(
-                stdout(),
+            queue!(
+                self.renderer,
                 ResetDefaultColors(false),
                 Clear(ClearType::All),
                 MoveTo((x / 2) - (warning_text.len() as u16 / 2), y / 2),
@@ -138,27 +762,57 @@ impl State {
             return Ok(());
         }
         self.term_too_small = false;
+        // The font size may have changed
+        if self.cell_size.is_some()
+            && let Some(cell) = CellSize::from_window()
+        {
+            self.cell_size = Some(cell);
+        }
         self.update_offset(x, y);
         self.offset_all();
-        execute!(stdout(), ResetDefaultColors(false), Clear(ClearType::All))?;
-        self.draw(false)?;
-        stdout().flush()?;
-        Ok(())
+        queue!(
+            self.renderer,
+            ResetDefaultColors(false),
+            Clear(ClearType::All)
+        )?;
+        self.draw(false)
     }
 
     pub fn handle_mouse_event(&mut self, event: MouseEvent) -> io::Result<()> {
+        // Clicks in the lower half of a cell pick its lower pixel when the
+        // mouse is reported in pixels, see `picker_row` otherwise
+        let (event, lower_half) = match self.cell_size {
+            Some(cell) => to_cells(event, cell),
+            None => (event, false),
+        };
+        if self.view == View::Image {
+            return self.handle_image_view_mouse_event(event, lower_half);
+        }
+        if let MouseEventKind::ScrollUp
+        | MouseEventKind::ScrollDown
+        | MouseEventKind::ScrollLeft
+        | MouseEventKind::ScrollRight = event.kind
+        {
+            return self.handle_scroll(event);
+        }
         if event.kind == MouseEventKind::Down(MouseButton::Left)
             || event.kind == MouseEventKind::Drag(MouseButton::Left)
         {
             if let Some(pos) = normalize_pos(event, &self.sv_picker.pos)
-                && let Ok(()) = self.sv_picker.change_color(pos.x, pos.y)
+                && let Ok(()) = self.sv_picker.change_color(
+                    pos.x,
+                    self.picker_row(pos.y, lower_half, self.sv_picker.height),
+                )
             {
                 self.draw_component(Component::ValueDisplay, false)?;
                 self.draw_component(Component::Inputs, false)?;
             }
 
             if let Some(pos) = normalize_pos(event, &self.hue_picker.pos)
-                && let Ok(hue) = self.hue_picker.get(pos.x, pos.y)
+                && let Ok(hue) = self.hue_picker.get(
+                    pos.x,
+                    self.picker_row(pos.y, lower_half, self.hue_picker.height),
+                )
             {
                 self.sv_picker.set_hue(hue);
                 self.draw_component(Component::ValueDisplay, false)?;
@@ -166,100 +820,756 @@ impl State {
                 self.draw_component(Component::SVPicker, false)?;
             }
 
+            if event.kind == MouseEventKind::Down(MouseButton::Left)
+                && let Some(pos) = normalize_pos(event, &self.history_strip.pos)
+                && let Ok(index) = self.history_strip.mouse_click(pos.x, pos.y)
+            {
+                self.restore_history(index)?;
+            }
+
+            if event.kind == MouseEventKind::Down(MouseButton::Left)
+                && let Some(pos) = normalize_pos(event, &self.palette_strip.pos)
+                && let Ok(index) = self.palette_strip.mouse_click(pos.x, pos.y)
+            {
+                self.select_palette_swatch(index)?;
+            }
+
+            let focused = self.inputs.focus;
             if let Some(pos) = normalize_pos(event, &self.inputs.pos)
-                && let Ok(()) = self.inputs.mouse_click(pos.x, pos.y)
+                && let Ok(column) = self.inputs.mouse_click(&mut self.renderer, pos.x, pos.y)
                 && event.kind != MouseEventKind::Drag(MouseButton::Left)
             {
-                self.inputs.gain_focus(&self.sv_picker.selected_color)?;
+                if self.inputs.focus != focused {
+                    self.focus_field(self.inputs.focus)?;
+                }
+                self.inputs.place_cursor(&mut self.renderer, column)?;
             } else {
-                let _ = self.inputs.lose_focus();
+                let _ = self.inputs.lose_focus(&mut self.renderer);
                 self.draw_component(Component::Inputs, false)?;
             }
         }
         Ok(())
     }
 
+    /// Pixel row of a picker `height` cells tall clicked at cell row `y`
+    fn picker_row(&self, y: u32, lower_half: bool, height: u32) -> u32 {
+        match self.cell_size {
+            Some(_) => y * 2 + lower_half as u32,
+            None => cell_row_to_pixel(y, height),
+        }
+    }
+
+    /// Magnifies the pickers around the selected color, `None` toggles the
+    /// default zoom
+    pub fn zoom(&mut self, factor: Option<f32>) -> io::Result<()> {
+        let factor = match factor {
+            Some(factor) => factor,
+            None if self.sv_picker.is_zoomed() => 1.0,
+            None => DEFAULT_ZOOM,
+        };
+        let hue = self.sv_picker.selected_color.hue.into_positive_degrees();
+        self.sv_picker.zoom(factor);
+        self.hue_picker.zoom(hue, factor);
+        if self.view == View::Picker {
+            self.draw_component(Component::SVPicker, false)?;
+            self.draw_component(Component::HuePicker, false)?;
+        }
+        if factor == 1.0 {
+            self.notify(MessageLevel::Info, "Zoomed out")
+        } else {
+            self.notify(
+                MessageLevel::Info,
+                format!("Zoomed in {}x around the color, z to zoom out", factor),
+            )
+        }
+    }
+
+    /// Reports the mouse in pixels from now on, to pick the lower half of
+    /// cells
+    pub fn enable_pixel_mouse(&mut self, cell: CellSize) -> io::Result<()> {
+        self.cell_size = Some(cell);
+        execute!(self.backend, Print(ENABLE_PIXEL_MOUSE))
+    }
+
+    /// Wheel over the hue strip nudges the hue, over the SV square the value
+    /// (the saturation with Shift or a horizontal wheel) and over an input
+    /// field its value. Ctrl makes the steps finer.
+    fn handle_scroll(&mut self, event: MouseEvent) -> io::Result<()> {
+        let (sign, horizontal) = match event.kind {
+            MouseEventKind::ScrollUp => (1.0, false),
+            MouseEventKind::ScrollDown => (-1.0, false),
+            MouseEventKind::ScrollRight => (1.0, true),
+            MouseEventKind::ScrollLeft => (-1.0, true),
+            _ => return Ok(()),
+        };
+        let divisor = if event.modifiers.contains(KeyModifiers::CONTROL) {
+            WHEEL_FINE_DIVISOR
+        } else {
+            1.0
+        };
+
+        if let Some(pos) = normalize_pos(event, &self.hue_picker.pos)
+            && self.hue_picker.get(pos.x, pos.y * 2).is_ok()
+        {
+            self.leave_field();
+            self.start_wheel_step(WheelTarget::Hue)?;
+            let hue = self.sv_picker.selected_color.hue.into_positive_degrees();
+            self.sv_picker
+                .set_hue((hue + sign * WHEEL_HUE_STEP / divisor).rem_euclid(360.0));
+        } else if let Some(pos) = normalize_pos(event, &self.sv_picker.pos)
+            && self.sv_picker.get(pos.x, pos.y * 2).is_ok()
+        {
+            self.leave_field();
+            self.start_wheel_step(WheelTarget::SVPicker)?;
+            let step = sign * WHEEL_SV_STEP / divisor;
+            if horizontal || event.modifiers.contains(KeyModifiers::SHIFT) {
+                self.sv_picker.nudge(step, 0.0);
+            } else {
+                self.sv_picker.nudge(0.0, step);
+            }
+        } else if let Some(pos) = normalize_pos(event, &self.inputs.pos)
+            && let Some(field) = self.inputs.field_at(pos.x, pos.y)
+            && field != Focus::Hex
+        {
+            let mut step = sign * self.inputs.step / divisor;
+            if !field.is_decimal() {
+                // RGB values are whole numbers
+                step = sign * step.abs().round().max(1.0);
+            }
+            if field == self.inputs.focus {
+                self.inputs.nudge(step);
+                return self.preview_field(field, self.inputs.preview_value());
+            }
+            self.leave_field();
+            self.start_wheel_step(WheelTarget::Field(field))?;
+            let color = self.sv_picker.selected_color;
+            let value = field.step(field.read(&color, &self.color_settings), step);
+            self.sv_picker.selected_color = self.apply_field(color, field, value);
+        } else {
+            return Ok(());
+        }
+        self.draw(false)?;
+        if self.inputs.focus != Focus::NONE {
+            self.inputs.draw_input_str(&mut self.renderer)?;
+        }
+        Ok(())
+    }
+
+    /// Records the color before a burst of wheel steps over the same target,
+    /// so that the burst can be undone from the recent colors
+    fn start_wheel_step(&mut self, target: WheelTarget) -> io::Result<()> {
+        let now = Instant::now();
+        let new_burst = match self.wheel_burst {
+            Some((last, time)) => last != target || now - time > WHEEL_BURST_GAP,
+            None => true,
+        };
+        self.wheel_burst = Some((target, now));
+        if new_burst {
+            self.record_history()?;
+        }
+        Ok(())
+    }
+
+    fn handle_image_view_mouse_event(
+        &mut self,
+        event: MouseEvent,
+        lower_half: bool,
+    ) -> io::Result<()> {
+        if !(event.kind == MouseEventKind::Down(MouseButton::Left)
+            || event.kind == MouseEventKind::Drag(MouseButton::Left))
+        {
+            return Ok(());
+        }
+        let Some(pos) = normalize_pos(event, &self.image_view.pos) else {
+            return Ok(());
+        };
+        // Without pixel reporting the click could be on either pixel of the cell
+        let color = match self.cell_size {
+            Some(_) => self.image_view.get(pos.x, pos.y * 2 + lower_half as u32),
+            None => self.image_view.get_cell(pos.x, pos.y),
+        };
+        if let Ok(color) = color {
+            self.set_srgb_color(&color);
+            self.draw_component(Component::ValueDisplay, false)?;
+        }
+        Ok(())
+    }
+
+    fn handle_image_view_key_event(&mut self, event: KeyEvent) -> io::Result<()> {
+        match event.code {
+            KeyCode::Esc => return self.set_view(View::Picker),
+            KeyCode::Left | KeyCode::Char('h') => self.image_view.pan_by(-IMAGE_VIEW_PAN_STEP, 0),
+            KeyCode::Right | KeyCode::Char('l') => self.image_view.pan_by(IMAGE_VIEW_PAN_STEP, 0),
+            KeyCode::Up | KeyCode::Char('k') => self.image_view.pan_by(0, -IMAGE_VIEW_PAN_STEP),
+            KeyCode::Down | KeyCode::Char('j') => self.image_view.pan_by(0, IMAGE_VIEW_PAN_STEP),
+            KeyCode::Char('+' | '=') => self.image_view.zoom(2.0),
+            KeyCode::Char('-') => self.image_view.zoom(0.5),
+            KeyCode::Char('f') => self.image_view.fit(),
+            KeyCode::Char('e') => return self.extract_palette(EXTRACT_DEFAULT_COUNT),
+            KeyCode::Char(c @ ('[' | ']')) => {
+                let radius = &mut self.image_view.sample_radius;
+                *radius = if c == '[' {
+                    radius.saturating_sub(1)
+                } else {
+                    (*radius + 1).min(IMAGE_VIEW_MAX_SAMPLE_RADIUS)
+                };
+                let message = format!("Sample radius: {}", radius);
+                return self.notify(MessageLevel::Info, message);
+            }
+            _ => return Ok(()),
+        }
+        self.draw_component(Component::ImageView, false)
+    }
+
+    /// Handles text received through bracketed paste. The text goes into the
+    /// focused input field if there is one, otherwise it is parsed as a color.
+    pub fn handle_paste_event(&mut self, text: &str) -> io::Result<()> {
+        let field_focused = self.inputs.focus != Focus::NONE;
+        let pasted_color = validate_pasted_color_in(text.trim(), &self.color_settings);
+        if !field_focused && pasted_color.is_none() {
+            return self.paste_color_literals(text);
+        }
+        let accepted = if field_focused {
+            self.inputs.paste(&mut self.renderer, text)
+        } else if let Some(color) = pasted_color {
+            self.set_color(color);
+            self.draw(false)?;
+            true
+        } else {
+            false
+        };
+        if !accepted {
+            let text = text.trim();
+            if field_focused {
+                let message = format!("Not a valid {} value: {}", self.inputs.focus.name(), text);
+                self.notify(MessageLevel::Error, message)?;
+            } else {
+                self.notify(MessageLevel::Error, format!("Not a color: {}", text))?;
+            }
+        } else if field_focused {
+            self.preview_field(self.inputs.focus, self.inputs.preview_value())?;
+        } else {
+            self.notify(MessageLevel::Success, "Pasted color")?;
+        }
+        Ok(())
+    }
+
+    /// Pasted text that is not just a color: a single literal in it is
+    /// selected, several are loaded as a palette.
+    fn paste_color_literals(&mut self, text: &str) -> io::Result<()> {
+        let matches = scan_text("paste", text);
+        match matches.as_slice() {
+            [] => self.notify(MessageLevel::Error, format!("Not a color: {}", text.trim())),
+            [single] => {
+                self.set_srgb_color(&single.literal.color);
+                self.draw(false)?;
+                self.notify(MessageLevel::Success, "Pasted color")
+            }
+            _ => {
+                self.load_palette(palette_from_matches(&matches, "pasted"))?;
+                self.notify(
+                    MessageLevel::Success,
+                    format!("Pasted {} colors in the palette", matches.len()),
+                )
+            }
+        }
+    }
+
     pub fn handle_key_event(&mut self, event: KeyEvent) -> io::Result<()> {
+        if self.command_line.active {
+            if let Some(command) = self.command_line.handle_key(event) {
+                self.draw_component(Component::CommandLine, false)?;
+                self.draw_component(Component::StatusBar, false)?;
+                return self.run_command(&command);
+            }
+            self.draw_component(Component::CommandLine, false)?;
+            if !self.command_line.active {
+                self.draw_component(Component::StatusBar, false)?;
+            }
+            return Ok(());
+        }
+
         if self.flags & COPY_FLAG != 0 {
-            handle_copy_input_format_selection_input(event, self.sv_picker.selected_color)?;
+            let copied = handle_copy_input_format_selection_input(
+                event,
+                self.sv_picker.selected_color,
+                &self.color_settings,
+                self.selection,
+            );
             self.flags &= !COPY_FLAG;
-            clear_clipboard_format_selector(COPY_FORMAT_SELECTOR_RES_POS + self.offset)?;
-            draw_copied_confirmation(COPY_FORMAT_SELECTOR_RES_POS + self.offset, false)?;
-            self.flags |= COPY_CONFIRMED_FLAG;
+            clear_clipboard_format_selector(
+                &mut self.renderer,
+                COPY_FORMAT_SELECTOR_RES_POS + self.offset,
+            )?;
+            if self.view == View::Image {
+                self.draw_component(Component::ImageView, false)?;
+            }
+            match copied {
+                Ok(Some((text, selection))) => {
+                    self.notify(
+                        MessageLevel::Success,
+                        format!("Copied {} to {}", text, selection.as_str()),
+                    )?;
+                    self.record_history()?;
+                }
+                Ok(None) => self.notify(MessageLevel::Info, "Copy cancelled")?,
+                Err(err) => self.notify(MessageLevel::Error, format!("Copy failed: {}", err))?,
+            }
             return Ok(());
         }
 
         if event.code == KeyCode::Char('q')
             || (event.code == KeyCode::Char('c') && event.modifiers.contains(KeyModifiers::CONTROL))
-            || event.code == KeyCode::Esc
+            || (event.code == KeyCode::Esc
+                && self.view == View::Picker
+                && self.inputs.focus == Focus::NONE)
         {
             self.flags |= EXIT_FLAG;
             return Ok(());
         }
+        if event.code == KeyCode::Enter
+            && self.inputs.focus == Focus::NONE
+            && self.edit_target.is_some()
+        {
+            return self.confirm_edit();
+        }
+        if event.code == KeyCode::Char(':') && self.inputs.focus == Focus::NONE {
+            self.command_line.activate();
+            return self.draw_component(Component::CommandLine, false);
+        }
+        if event.code == KeyCode::Char('i') && self.inputs.focus == Focus::NONE {
+            let view = if self.view == View::Image {
+                View::Picker
+            } else {
+                View::Image
+            };
+            return self.set_view(view);
+        }
         if event.code == KeyCode::Char('y') {
             draw_clipboard_format_selector(
+                &mut self.renderer,
                 COPY_FORMAT_SELECTOR_RES_POS + self.offset,
                 self.sv_picker.selected_color,
+                &self.color_settings,
                 false,
             )?;
             self.flags |= COPY_FLAG;
             return Ok(());
         }
-        if event.code == KeyCode::Char('p') {
-            if let Some(clipboard_content) = clipboard_paste() {
-                self.sv_picker.selected_color = clipboard_content;
-                self.draw(false)?;
-                draw_pasted_confirmation(COPY_FORMAT_SELECTOR_RES_POS + self.offset, false)?;
-                self.flags |= PASTE_CONFIRMED_FLAG;
+        if let KeyCode::Char(c @ ('p' | 'P')) = event.code {
+            let selection = if c == 'P' {
+                Selection::Primary
+            } else {
+                self.selection
+            };
+            match clipboard_paste(selection, &self.color_settings) {
+                Ok(Some(clipboard_content)) => {
+                    self.set_color(clipboard_content);
+                    self.draw(false)?;
+                    self.notify(
+                        MessageLevel::Success,
+                        format!("Pasted color from {}", selection.as_str()),
+                    )?;
+                }
+                Ok(None) => self.notify(
+                    MessageLevel::Error,
+                    format!("No color in {}", selection.as_str()),
+                )?,
+                Err(err) => self.notify(
+                    MessageLevel::Error,
+                    format!("Could not read {}: {}", selection.as_str(), err),
+                )?,
             }
         }
 
-        match self.inputs.value_input(event.code) {
-            Some((focus, value)) => {
-                match focus {
-                    Focus::Hex => {
-                        let r = ((value >> 16) & 0xFF) as u8;
-                        let g = ((value >> 8) & 0xFF) as u8;
-                        let b = (value & 0xFF) as u8;
-                        self.sv_picker.selected_color = hsv_from_rgb(r, g, b)
-                    }
-                    Focus::R => {
-                        let (_, g, b) = rgb_from_hsv(&self.sv_picker.selected_color);
-                        let r = value.min(255) as u8;
-                        self.sv_picker.selected_color = hsv_from_rgb(r, g, b)
-                    }
-                    Focus::G => {
-                        let (r, _, b) = rgb_from_hsv(&self.sv_picker.selected_color);
-                        let g = value.min(255) as u8;
-                        self.sv_picker.selected_color = hsv_from_rgb(r, g, b)
-                    }
-                    Focus::B => {
-                        let (r, g, _) = rgb_from_hsv(&self.sv_picker.selected_color);
-                        let b = value.min(255) as u8;
-                        self.sv_picker.selected_color = hsv_from_rgb(r, g, b)
-                    }
-                    Focus::H => {
-                        self.sv_picker
-                            .selected_color
-                            .set_hue(RgbHue::from_degrees(value as f32));
-                    }
-                    Focus::S => {
-                        self.sv_picker.selected_color.saturation = (value.min(100) as f32) / 100.0;
-                    }
-                    Focus::V => {
-                        self.sv_picker.selected_color.value = (value.min(100) as f32) / 100.0;
-                    }
-                    _ => {}
-                }
+        if self.view == View::Image {
+            return self.handle_image_view_key_event(event);
+        }
+
+        if event.code == KeyCode::Char('z') && self.inputs.focus == Focus::NONE {
+            return self.zoom(None);
+        }
+
+        if self.inputs.focus == Focus::NONE
+            && let KeyCode::Char(c) = event.code
+            && let Some(digit) = c.to_digit(10)
+        {
+            // 1 selects the most recent color and 0 the tenth
+            return self.restore_history((digit as usize + 9) % 10);
+        }
+
+        if let KeyCode::Tab | KeyCode::BackTab = event.code {
+            return self.cycle_focus(event.code == KeyCode::BackTab);
+        }
+
+        match self.inputs.value_input(&mut self.renderer, event) {
+            Some(FieldEvent::Validated(focus, Err(()))) => {
+                self.sv_picker.selected_color = self.pre_edit_color;
                 self.draw(false)?;
+                self.notify(
+                    MessageLevel::Error,
+                    format!("Invalid {} value", focus.name()),
+                )?;
             }
-            None => {
-                if self.inputs.focus == Focus::NONE {
-                    let _ = self.inputs.lose_focus();
-                    self.draw_component(Component::Inputs, false)?;
-                }
+            Some(FieldEvent::Validated(focus, Ok(value))) => {
+                self.sv_picker.selected_color = self.apply_field(self.pre_edit_color, focus, value);
+                self.draw(false)?;
+                self.record_history()?;
             }
+            Some(FieldEvent::Edited(focus, value)) => self.preview_field(focus, value)?,
+            Some(FieldEvent::Cancelled) => {
+                self.sv_picker.selected_color = self.pre_edit_color;
+                self.draw(false)?;
+            }
+            None => {}
         }
         Ok(())
     }
+
+    /// `color` after `value` is entered in `field`
+    fn apply_field(&self, mut edited: Hsv, field: Focus, value: f32) -> Hsv {
+        let color = &mut edited;
+        let settings = self.color_settings;
+        match field {
+            Focus::Hex => {
+                let value = value as u32;
+                let r = ((value >> 16) & 0xFF) as u8;
+                let g = ((value >> 8) & 0xFF) as u8;
+                let b = (value & 0xFF) as u8;
+                *color = keep_hue(settings.from_srgb(&hsv_from_rgb(r, g, b)), color)
+            }
+            Focus::R | Focus::G | Focus::B => {
+                let channel = match field {
+                    Focus::R => 0,
+                    Focus::G => 1,
+                    _ => 2,
+                };
+                *color = with_rgb_channel(color, channel, value / 255.0);
+            }
+            Focus::H => {
+                color.set_hue(RgbHue::from_degrees(value));
+            }
+            Focus::S => {
+                color.saturation = value.min(100.0) / 100.0;
+            }
+            Focus::V => {
+                color.value = value.min(100.0) / 100.0;
+            }
+            Focus::C | Focus::M | Focus::Y | Focus::K => {
+                let (mut c, mut m, mut y, mut k) = cmyk_from_hsv(&settings.srgb(color));
+                let ink = match field {
+                    Focus::C => &mut c,
+                    Focus::M => &mut m,
+                    Focus::Y => &mut y,
+                    _ => &mut k,
+                };
+                *ink = value / 100.0;
+                *color = keep_hue(settings.from_srgb(&hsv_from_cmyk(c, m, y, k)), color);
+            }
+            Focus::LabL | Focus::LabA | Focus::LabB => {
+                let (mut l, mut a, mut b) = settings.lab(color);
+                let component = match field {
+                    Focus::LabL => &mut l,
+                    Focus::LabA => &mut a,
+                    _ => &mut b,
+                };
+                *component = value;
+                *color = keep_hue(settings.from_lab(l, a, b), color);
+            }
+            Focus::LchC | Focus::LchH => {
+                let (l, mut c, mut h) = settings.lch(color);
+                let component = match field {
+                    Focus::LchC => &mut c,
+                    _ => &mut h,
+                };
+                *component = value;
+                *color = keep_hue(settings.from_lch(l, c, h), color);
+            }
+            Focus::XyzX | Focus::XyzY | Focus::XyzZ => {
+                let (mut x, mut y, mut z) = settings.xyz(color);
+                let component = match field {
+                    Focus::XyzX => &mut x,
+                    Focus::XyzY => &mut y,
+                    _ => &mut z,
+                };
+                *component = value / 100.0;
+                *color = keep_hue(settings.from_xyz_components(x, y, z), color);
+            }
+            Focus::NONE => {}
+        }
+        edited
+    }
+
+    /// Shows the value being typed in `field`, or the color before the edit
+    /// while it is not a valid value
+    fn preview_field(&mut self, field: Focus, value: Option<f32>) -> io::Result<()> {
+        // Previewed from the color before the edit so that the values typed
+        // on the way do not add up
+        self.sv_picker.selected_color = match value {
+            Some(value) => self.apply_field(self.pre_edit_color, field, value),
+            None => self.pre_edit_color,
+        };
+        self.draw(false)?;
+        self.inputs.draw_input_str(&mut self.renderer)
+    }
+
+    /// Focuses an input field, or the pickers with `Focus::NONE`
+    fn focus_field(&mut self, field: Focus) -> io::Result<()> {
+        self.inputs.focus = field;
+        if field == Focus::NONE {
+            let _ = self.inputs.lose_focus(&mut self.renderer);
+            return self.draw_component(Component::Inputs, false);
+        }
+        self.pre_edit_color = self.sv_picker.selected_color;
+        self.inputs.gain_focus(
+            &mut self.renderer,
+            &self.sv_picker.selected_color,
+            &self.color_settings,
+        )
+    }
+
+    /// Tab and Shift-Tab: validates the focused field and focuses the next or
+    /// previous one. An invalid value keeps the focus in its field.
+    fn cycle_focus(&mut self, backward: bool) -> io::Result<()> {
+        let focus = self.inputs.focus;
+        if focus != Focus::NONE && self.inputs.modified {
+            let Ok(value) = self.inputs.parse() else {
+                return self.notify(
+                    MessageLevel::Error,
+                    format!("Invalid {} value", focus.name()),
+                );
+            };
+            self.sv_picker.selected_color = self.apply_field(self.pre_edit_color, focus, value);
+            self.record_history()?;
+        }
+        let next = if backward {
+            focus.previous()
+        } else {
+            focus.next()
+        };
+        self.draw(false)?;
+        self.focus_field(next)
+    }
+}
+
+#[cfg(test)]
+fn headless_state() -> State {
+    use crate::backend::HeadlessBackend;
+
+    let (width, height) = (120, 50);
+    let mut app = State::new(
+        SaturationValuePicker::new(SV_PICKER_REL_POS, SV_PICKER_WIDTH, SV_PICKER_HEIGHT),
+        HuePicker::new(HUE_PICKER_REL_POS, HUE_PICKER_WIDTH, HUE_PICKER_HEIGHT),
+        Inputs::new(INPUTS_REL_POS),
+        StatusBar::new(STATUS_BAR_REL_POS, TOTAL_WIDTH),
+        SwatchStrip::new(
+            HISTORY_STRIP_REL_POS,
+            "Recent:",
+            HISTORY_SWATCH_WIDTH,
+            HISTORY_LEN,
+            HISTORY_LEN,
+            true,
+        ),
+        width,
+        height,
+        Box::new(HeadlessBackend),
+    )
+    .unwrap();
+    app.update_offset(width, height);
+    app.offset_all();
+    app.set_srgb_color(&hsv_from_rgb(0xff, 0x57, 0x33));
+    app.draw(false).unwrap();
+    app
+}
+
+/// Sends the frame to `terminal`, a screen standing for the real terminal,
+/// and checks it shows what was drawn
+#[cfg(test)]
+fn present_to(app: &mut State, terminal: &mut crate::render::Screen) -> String {
+    app.renderer.present(terminal).unwrap();
+    let text = terminal.text();
+    assert_eq!(text, app.renderer.screen().text());
+    assert_eq!(terminal.styles(), app.renderer.screen().styles());
+    text
+}
+
+#[test]
+fn test_layout_snapshot() {
+    use crate::render::{Color, Screen};
+
+    let mut app = headless_state();
+    let mut terminal = Screen::new(120, 50);
+    let text = present_to(&mut app, &mut terminal);
+    assert_eq!(text, include_str!("../tests/golden/layout.txt"));
+    assert_eq!(
+        terminal.styles(),
+        include_str!("../tests/golden/layout_styles.txt")
+    );
+    assert!(!terminal.cursor_visible);
+    // Nothing is drawn past the width checked against the terminal size
+    let offset = (120 - TOTAL_WIDTH as usize) / 2;
+    for line in text.lines() {
+        assert!(line.chars().count() <= offset + TOTAL_WIDTH as usize);
+    }
+
+    let swatch = terminal.cell(11, 7).unwrap();
+    assert_eq!(swatch.style.fg, Some(Color::Rgb(0xff, 0x57, 0x33)));
+    let label = terminal.cell(22, 7).unwrap();
+    assert_eq!(label.style.fg, Some(Color::Rgb(0xff, 0xff, 0xff)));
+    assert!(label.style.bold);
+    // The top left corner of the saturation and value square is white
+    let corner = terminal.cell(11, 10).unwrap();
+    assert_eq!(corner.style.bg, Some(Color::Rgb(0xff, 0xff, 0xff)));
+}
+
+#[test]
+fn test_focus_snapshot() {
+    use crate::render::Screen;
+
+    let mut app = headless_state();
+    let mut terminal = Screen::new(120, 50);
+    let layout = present_to(&mut app, &mut terminal);
+    let before = terminal.clone();
+
+    // Tab focuses the hex field with its text selected, nothing else changes
+    app.handle_key_event(KeyEvent::from(KeyCode::Tab)).unwrap();
+    assert_eq!(present_to(&mut app, &mut terminal), layout);
+    assert!(terminal.cursor_visible);
+    assert_eq!(terminal.cursor, (90, 14));
+    for y in 0..50 {
+        for x in 0..120 {
+            let mut expected = *before.cell(x, y).unwrap();
+            expected.style.reverse = y == 14 && (84..90).contains(&x);
+            assert_eq!(terminal.cell(x, y), Some(&expected), "cell {x}, {y}");
+        }
+    }
+
+    // Typing replaces the selection and previews the value
+    for c in "00ff00".chars() {
+        app.handle_key_event(KeyEvent::from(KeyCode::Char(c)))
+            .unwrap();
+    }
+    let text = present_to(&mut app, &mut terminal);
+    assert_eq!(text, include_str!("../tests/golden/focus_hex.txt"));
+    assert_eq!(
+        terminal.styles(),
+        include_str!("../tests/golden/focus_hex_styles.txt")
+    );
+    assert!(!terminal.cell(84, 14).unwrap().style.reverse);
+}
+
+#[test]
+fn test_faded_snapshot() {
+    use crate::render::{Color, Screen};
+
+    let mut app = headless_state();
+    app.draw(true).unwrap();
+    let mut terminal = Screen::new(120, 50);
+    // Fading changes colors only
+    let text = present_to(&mut app, &mut terminal);
+    assert_eq!(text, include_str!("../tests/golden/layout.txt"));
+    assert_eq!(
+        terminal.styles(),
+        include_str!("../tests/golden/faded_styles.txt")
+    );
+
+    let faded = FADED_TEXT_COLOR;
+    let label = terminal.cell(22, 7).unwrap();
+    assert_eq!(label.style.fg, Some(Color::Rgb(faded.r, faded.g, faded.b)));
+    let corner = terminal.cell(11, 10).unwrap();
+    assert_ne!(corner.style.bg, Some(Color::Rgb(0xff, 0xff, 0xff)));
+}
+
+#[test]
+fn test_edit_unreported_slot() {
+    let mut app = headless_state();
+    app.terminal_palette.set(Slot::Ansi(1), (0xcc, 0x00, 0x00));
+    app.run_command("slot cursor").unwrap();
+    assert_eq!(app.editing_slot, None);
+    assert_eq!(app.terminal_palette.get(Slot::Cursor), None);
+
+    app.run_command("slot 1").unwrap();
+    assert_eq!(app.editing_slot, Some(Slot::Ansi(1)));
+    app.set_srgb_color(&hsv_from_rgb(0x00, 0xcc, 0x00));
+    app.sync_terminal_slot().unwrap();
+    assert_eq!(
+        app.terminal_palette.get(Slot::Ansi(1)),
+        Some((0x00, 0xcc, 0x00))
+    );
+    assert_eq!(app.terminal_palette.get(Slot::Cursor), None);
+}
+
+#[test]
+fn test_outside_change_leaves_field() {
+    let mut app = headless_state();
+    app.handle_key_event(KeyEvent::from(KeyCode::Tab)).unwrap();
+    for c in "00ff00".chars() {
+        app.handle_key_event(KeyEvent::from(KeyCode::Char(c)))
+            .unwrap();
+    }
+    // Set over the socket while the hex field previews green
+    app.set_srgb_color(&hsv_from_rgb(0x00, 0x00, 0xff));
+    assert_eq!(app.inputs.focus, Focus::NONE);
+    assert_eq!(rgb_from_hsv(&app.srgb_color()), (0x00, 0x00, 0xff));
+
+    // The wheel over the hue strip
+    app.handle_key_event(KeyEvent::from(KeyCode::Tab)).unwrap();
+    let pos = app.hue_picker.pos;
+    app.handle_mouse_event(MouseEvent {
+        kind: MouseEventKind::ScrollUp,
+        column: pos.x as u16,
+        row: pos.y as u16 + 5,
+        modifiers: KeyModifiers::NONE,
+    })
+    .unwrap();
+    assert_eq!(app.inputs.focus, Focus::NONE);
+    assert_ne!(rgb_from_hsv(&app.srgb_color()), (0x00, 0x00, 0xff));
+}
+
+#[test]
+fn test_wheel_history() {
+    let mut app = headless_state();
+    let scroll = |app: &mut State, pos: Vec2| {
+        app.handle_mouse_event(MouseEvent {
+            kind: MouseEventKind::ScrollUp,
+            column: pos.x as u16,
+            row: pos.y as u16 + 5,
+            modifiers: KeyModifiers::NONE,
+        })
+        .unwrap();
+    };
+    let start = rgb_from_hsv(&app.srgb_color());
+    // A burst over the hue strip records the color it started from once
+    let hue_picker = app.hue_picker.pos;
+    for _ in 0..3 {
+        scroll(&mut app, hue_picker);
+    }
+    assert_eq!(app.history.colors.len(), 1);
+    assert_eq!(rgb_from_hsv(&app.history.colors[0]), start);
+
+    let after_hue = rgb_from_hsv(&app.srgb_color());
+    let sv_picker = app.sv_picker.pos;
+    scroll(&mut app, sv_picker);
+    assert_eq!(app.history.colors.len(), 2);
+    assert_eq!(rgb_from_hsv(&app.history.colors[0]), after_hue);
+}
+
+#[test]
+fn test_sv_picker_mouse() {
+    let mut app = headless_state();
+    let (pos, height) = (app.sv_picker.pos, app.sv_picker.height);
+    let click = |app: &mut State, row: u32| {
+        app.handle_mouse_event(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: pos.x as u16,
+            row: row as u16,
+            modifiers: KeyModifiers::NONE,
+        })
+        .unwrap();
+    };
+    // Without pixel reporting the last row of cells reaches black
+    click(&mut app, pos.y + height - 1);
+    assert_eq!(app.sv_picker.selected_color.value, 0.0);
+    click(&mut app, pos.y);
+    assert_eq!(app.sv_picker.selected_color.value, 1.0);
 }
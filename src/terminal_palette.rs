@@ -0,0 +1,329 @@
+use crate::color_palette::ColorPalette;
+use crate::utils::hsv_from_rgb;
+use std::io;
+use std::time::Duration;
+
+/// Colors reported by the terminal, `None` when it did not answer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TerminalPalette {
+    pub ansi: [Option<(u8, u8, u8)>; 16],
+    pub foreground: Option<(u8, u8, u8)>,
+    pub background: Option<(u8, u8, u8)>,
+    pub cursor: Option<(u8, u8, u8)>,
+}
+
+/// Dynamic color of the terminal that can be changed with an OSC sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Ansi(usize),
+    Foreground,
+    Background,
+    Cursor,
+}
+
+/// Name of the palette loaded in the palette strip from the terminal
+pub const TERMINAL_PALETTE_NAME: &str = "terminal";
+
+/// Resets the ANSI colors, foreground, background and cursor colors to the
+/// values from the terminal configuration.
+pub const RESET_PALETTE_SEQUENCE: &str = "\x1b]104\x1b\\\x1b]110\x1b\\\x1b]111\x1b\\\x1b]112\x1b\\";
+
+const ANSI_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright black",
+    "bright red",
+    "bright green",
+    "bright yellow",
+    "bright blue",
+    "bright magenta",
+    "bright cyan",
+    "bright white",
+];
+
+impl Slot {
+    /// Accepts an ANSI color index or `fg`, `bg` and `cursor`
+    pub fn parse(s: &str) -> Option<Slot> {
+        match s.to_ascii_lowercase().as_str() {
+            "fg" | "foreground" => Some(Slot::Foreground),
+            "bg" | "background" => Some(Slot::Background),
+            "cursor" => Some(Slot::Cursor),
+            s => {
+                let index = s.strip_prefix("color").unwrap_or(s).parse().ok()?;
+                (index < 16).then_some(Slot::Ansi(index))
+            }
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Slot::Ansi(index) => format!("color{} ({})", index, ANSI_NAMES[*index]),
+            Slot::Foreground => "foreground".to_string(),
+            Slot::Background => "background".to_string(),
+            Slot::Cursor => "cursor".to_string(),
+        }
+    }
+
+    /// OSC sequence changing the slot to the color
+    pub fn set_sequence(&self, (r, g, b): (u8, u8, u8)) -> String {
+        let spec = format!("rgb:{:02x}/{:02x}/{:02x}", r, g, b);
+        match self {
+            Slot::Ansi(index) => format!("\x1b]4;{};{}\x1b\\", index, spec),
+            Slot::Foreground => format!("\x1b]10;{}\x1b\\", spec),
+            Slot::Background => format!("\x1b]11;{}\x1b\\", spec),
+            Slot::Cursor => format!("\x1b]12;{}\x1b\\", spec),
+        }
+    }
+}
+
+impl TerminalPalette {
+    pub fn get(&self, slot: Slot) -> Option<(u8, u8, u8)> {
+        match slot {
+            Slot::Ansi(index) => self.ansi[index],
+            Slot::Foreground => self.foreground,
+            Slot::Background => self.background,
+            Slot::Cursor => self.cursor,
+        }
+    }
+
+    pub fn set(&mut self, slot: Slot, rgb: (u8, u8, u8)) {
+        let color = match slot {
+            Slot::Ansi(index) => &mut self.ansi[index],
+            Slot::Foreground => &mut self.foreground,
+            Slot::Background => &mut self.background,
+            Slot::Cursor => &mut self.cursor,
+        };
+        *color = Some(rgb);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == TerminalPalette::default()
+    }
+
+    /// Swatches for the answered colors, labeled with the slot
+    pub fn to_palette(&self) -> ColorPalette {
+        let mut palette = ColorPalette::new(TERMINAL_PALETTE_NAME);
+        for (i, color) in self.ansi.iter().enumerate() {
+            if let Some((r, g, b)) = *color {
+                palette.push(
+                    format!("color{} ({})", i, ANSI_NAMES[i]),
+                    hsv_from_rgb(r, g, b),
+                );
+            }
+        }
+        if let Some((r, g, b)) = self.foreground {
+            palette.push("foreground", hsv_from_rgb(r, g, b));
+        }
+        if let Some((r, g, b)) = self.background {
+            palette.push("background", hsv_from_rgb(r, g, b));
+        }
+        if let Some((r, g, b)) = self.cursor {
+            palette.push("cursor", hsv_from_rgb(r, g, b));
+        }
+        palette
+    }
+}
+
+/// Parses an X11 color specification as used in OSC replies:
+/// `rgb:r/g/b` with 1 to 4 hex digits per channel, or `#rgb` style.
+pub fn parse_osc_color(spec: &str) -> Option<(u8, u8, u8)> {
+    fn scale(hex: &str) -> Option<u8> {
+        if hex.is_empty() || hex.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        let max = (1u32 << (4 * hex.len())) - 1;
+        Some(((value * 255 + max / 2) / max) as u8)
+    }
+
+    if let Some(channels) = spec
+        .strip_prefix("rgb:")
+        .or_else(|| spec.strip_prefix("rgba:"))
+    {
+        let mut parts = channels.split('/');
+        let r = scale(parts.next()?)?;
+        let g = scale(parts.next()?)?;
+        let b = scale(parts.next()?)?;
+        return Some((r, g, b));
+    }
+    let hex = spec.strip_prefix('#')?;
+    if hex.is_empty() || hex.len() % 3 != 0 || hex.len() > 12 {
+        return None;
+    }
+    // `#rgb` style specifications give the most significant bits
+    let n = hex.len() / 3;
+    let channel = |i: usize| {
+        let digits = &hex[i * n..(i + 1) * n];
+        u8::from_str_radix(&format!("{:0<2}", &digits[..n.min(2)]), 16).ok()
+    };
+    Some((channel(0)?, channel(1)?, channel(2)?))
+}
+
+/// Extracts the OSC 4, 10, 11 and 12 replies from raw terminal input. Replies
+/// end with either BEL or ST, anything else in the input is ignored.
+pub fn parse_replies(input: &[u8]) -> TerminalPalette {
+    let mut palette = TerminalPalette::default();
+    let input = String::from_utf8_lossy(input);
+    for sequence in input.split("\x1b]").skip(1) {
+        let end = sequence.find(['\x07', '\x1b']).unwrap_or(sequence.len());
+        let mut fields = sequence[..end].split(';');
+        match fields.next() {
+            Some("4") => {
+                while let (Some(index), Some(spec)) = (fields.next(), fields.next()) {
+                    if let Ok(index) = index.parse::<usize>()
+                        && index < 16
+                    {
+                        palette.ansi[index] = parse_osc_color(spec);
+                    }
+                }
+            }
+            Some("10") => palette.foreground = fields.next().and_then(parse_osc_color),
+            Some("11") => palette.background = fields.next().and_then(parse_osc_color),
+            Some("12") => palette.cursor = fields.next().and_then(parse_osc_color),
+            _ => {}
+        }
+    }
+    palette
+}
+
+/// OSC 4, 10, 11 and 12 queries of the 16 ANSI colors, the foreground, the
+/// background and the cursor color
+pub fn palette_query() -> String {
+    let mut query = String::new();
+    for i in 0..16 {
+        query.push_str(&format!("\x1b]4;{};?\x1b\\", i));
+    }
+    query.push_str("\x1b]10;?\x1b\\\x1b]11;?\x1b\\\x1b]12;?\x1b\\");
+    query
+}
+
+/// Asks the terminal for its palette
+pub fn query_terminal_palette(timeout: Duration) -> io::Result<TerminalPalette> {
+    Ok(parse_replies(&query_terminal(&palette_query(), timeout)?))
+}
+
+/// Sends queries to the terminal and returns its raw replies. The queries are
+/// followed by a primary device attributes request, which every terminal
+/// answers, so we stop reading as soon as its reply arrives instead of always
+/// waiting for the timeout. The terminal is reached through `/dev/tty` so the
+/// standard output can be redirected. Must be called in raw mode, before
+/// crossterm starts reading events.
+#[cfg(unix)]
+pub fn query_terminal(query: &str, timeout: Duration) -> io::Result<Vec<u8>> {
+    use std::io::Write;
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")?;
+
+    tty.write_all(query.as_bytes())?;
+    tty.write_all(b"\x1b[c")?;
+    tty.flush()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut input = Vec::with_capacity(1024);
+    let mut buf = [0u8; 512];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let mut fd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a valid pollfd for the duration of the call
+        let ready = unsafe { libc::poll(&mut fd, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            break;
+        }
+        // SAFETY: `buf` is valid for writes of its length
+        let read = unsafe { libc::read(tty.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+        if read <= 0 {
+            break;
+        }
+        input.extend_from_slice(&buf[..read as usize]);
+        if device_attributes_received(&input) {
+            break;
+        }
+    }
+    Ok(input)
+}
+
+#[cfg(not(unix))]
+pub fn query_terminal(_query: &str, _timeout: Duration) -> io::Result<Vec<u8>> {
+    Ok(Vec::new())
+}
+
+/// The reply to `CSI c` is `CSI ? ... c`
+fn device_attributes_received(input: &[u8]) -> bool {
+    input.windows(3).enumerate().any(|(i, window)| {
+        window == b"\x1b[?"
+            && input[i + 3..]
+                .iter()
+                .find(|b| !(b.is_ascii_digit() || **b == b';'))
+                == Some(&b'c')
+    })
+}
+
+#[test]
+fn test_parse_osc_color() {
+    assert_eq!(parse_osc_color("rgb:ffff/5757/3333"), Some((255, 87, 51)));
+    assert_eq!(parse_osc_color("rgb:ff/57/33"), Some((255, 87, 51)));
+    assert_eq!(parse_osc_color("rgb:f/8/0"), Some((255, 136, 0)));
+    assert_eq!(parse_osc_color("rgb:1d1d/1d1d/1d1d"), Some((29, 29, 29)));
+    assert_eq!(parse_osc_color("rgba:0000/0000/0000/ffff"), Some((0, 0, 0)));
+    assert_eq!(parse_osc_color("#ff5733"), Some((255, 87, 51)));
+    assert_eq!(parse_osc_color("#ffff57573333"), Some((255, 87, 51)));
+    assert_eq!(parse_osc_color("rgb:ffff/5757"), None);
+    assert_eq!(parse_osc_color("rgb:fffff/0/0"), None);
+    assert_eq!(parse_osc_color("rgb:gg/00/00"), None);
+    assert_eq!(parse_osc_color("red"), None);
+}
+
+#[test]
+fn test_parse_replies() {
+    use crate::scheme::{Scheme, SchemeFormat};
+
+    let input = b"\x1b]4;1;rgb:cccc/0000/0000\x1b\\\x1b]4;15;rgb:ffff/ffff/ffff\x07\
+        \x1b]10;rgb:d0d0/d0d0/d0d0\x1b\\\x1b]11;rgb:1d1d/1d1d/1d1d\x07\x1b[?62;22c";
+    let palette = parse_replies(input);
+    assert_eq!(palette.ansi[0], None);
+    assert_eq!(palette.ansi[1], Some((204, 0, 0)));
+    assert_eq!(palette.ansi[15], Some((255, 255, 255)));
+    assert_eq!(palette.foreground, Some((208, 208, 208)));
+    assert_eq!(palette.background, Some((29, 29, 29)));
+    assert_eq!(palette.to_palette().swatches.len(), 4);
+    assert!(device_attributes_received(input));
+
+    let mut edited = palette.clone();
+    edited.set(Slot::Ansi(1), (255, 87, 51));
+    edited.set(Slot::parse("cursor").unwrap(), (255, 255, 255));
+    assert_eq!(
+        edited.get(Slot::parse("color1").unwrap()),
+        Some((255, 87, 51))
+    );
+    assert_eq!(
+        Slot::Ansi(1).set_sequence((255, 87, 51)),
+        "\x1b]4;1;rgb:ff/57/33\x1b\\"
+    );
+    assert!(
+        SchemeFormat::Shell
+            .write(&Scheme::from_terminal_palette(&edited, "edited"))
+            .contains("printf '\\033]4;1;rgb:ff/57/33\\033\\\\'\n")
+    );
+    assert_eq!(Slot::parse("16"), None);
+
+    // Terminal that only answers the device attributes request
+    assert!(parse_replies(b"\x1b[?1;2c").is_empty());
+}
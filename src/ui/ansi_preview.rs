@@ -0,0 +1,53 @@
+use crate::constants::*;
+use crate::crossterm_commands::{FillRect, ResetDefaultColors};
+use crate::terminal_palette::Slot;
+use crate::types::Vec2;
+use std::io::{self, Write};
+
+use crossterm::{
+    QueueableCommand,
+    cursor::MoveTo,
+    style::{Color, Print, SetBackgroundColor, SetForegroundColor},
+};
+
+/// Row drawn with the terminal's own palette while a slot is edited, so the
+/// live changes are visible even though the rest of the UI uses true colors.
+pub fn draw_ansi_preview(
+    out: &mut impl Write,
+    pos: &Vec2,
+    editing: Option<Slot>,
+    fade: bool,
+) -> io::Result<()> {
+    let mut buf: Vec<u8> = Vec::with_capacity(512);
+    buf.queue(ResetDefaultColors(fade))?;
+    buf.queue(FillRect(pos, TOTAL_WIDTH as u16, 1))?;
+    buf.queue(MoveTo(pos.x as u16, pos.y as u16))?;
+    if let Some(slot) = editing {
+        buf.queue(SetForegroundColor(Color::Reset))?;
+        for i in 0..16 {
+            buf.queue(SetBackgroundColor(Color::AnsiValue(i as u8)))?;
+            buf.queue(Print(if slot == Slot::Ansi(i) { "<>" } else { "  " }))?;
+        }
+        buf.queue(SetBackgroundColor(Color::Reset))?;
+        buf.queue(Print(format!(" Editing {} ", slot.name())))?;
+        buf.queue(ResetDefaultColors(fade))?;
+        buf.queue(Print(SPACE))?;
+    }
+    out.write_all(&buf)
+}
+
+#[test]
+fn test_ansi_preview_width() {
+    use crate::render::Screen;
+
+    let mut screen = Screen::new(TOTAL_WIDTH as u16 + 10, 1);
+    screen
+        .write_all(&[b'x'; TOTAL_WIDTH as usize + 10])
+        .unwrap();
+    draw_ansi_preview(&mut screen, &Vec2 { x: 5, y: 0 }, None, false).unwrap();
+    // Only the width of the panel is cleared, from its position
+    let row = screen.row_text(0);
+    assert_eq!(row.len(), TOTAL_WIDTH as usize + 10);
+    assert!(row.starts_with("xxxxx "));
+    assert!(row.ends_with(" xxxxx"));
+}
@@ -0,0 +1,71 @@
+use crate::crossterm_commands::{FillRect, ResetDefaultColors};
+use crate::types::Vec2;
+use std::io::{self, Write};
+
+use crossterm::{
+    QueueableCommand,
+    cursor::{Hide, MoveTo, Show},
+    event::{KeyCode, KeyEvent},
+    style::Print,
+};
+
+/// Vim-like `:` prompt drawn over the status bar.
+pub struct CommandLine {
+    pub buf: Vec<u8>,
+    pub pos: Vec2,
+    pub width: u32,
+    pub input: String,
+    pub active: bool,
+}
+
+impl CommandLine {
+    pub fn new(pos: Vec2, width: u32) -> Self {
+        CommandLine {
+            pos,
+            width,
+            input: String::new(),
+            active: false,
+            buf: Vec::with_capacity(width as usize * 2),
+        }
+    }
+
+    pub fn activate(&mut self) {
+        self.active = true;
+        self.input.clear();
+    }
+
+    /// Edits the command. Returns the command when it is validated with
+    /// enter, escape closes the prompt without running anything.
+    pub fn handle_key(&mut self, event: KeyEvent) -> Option<String> {
+        match event.code {
+            KeyCode::Enter => {
+                self.active = false;
+                return Some(self.input.trim().to_string());
+            }
+            KeyCode::Esc => self.active = false,
+            // Backspace on an empty prompt closes it
+            KeyCode::Backspace => self.active = self.input.pop().is_some(),
+            KeyCode::Char(c) if self.input.chars().count() + 2 < self.width as usize => {
+                self.input.push(c);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    pub fn draw(&mut self, out: &mut impl Write, fade: bool) -> io::Result<()> {
+        self.buf.clear();
+        self.buf.queue(ResetDefaultColors(fade))?;
+        self.buf.queue(FillRect(&self.pos, self.width as u16, 1))?;
+        if self.active {
+            self.buf
+                .queue(MoveTo(self.pos.x as u16, self.pos.y as u16))?;
+            self.buf.queue(Print(format!(":{}", self.input)))?;
+            self.buf.queue(Show)?;
+        } else {
+            self.buf.queue(Hide)?;
+        }
+        out.write_all(&self.buf)?;
+        Ok(())
+    }
+}
@@ -1,8 +1,9 @@
+use crate::color_space::ColorSettings;
 use crate::crossterm_commands::ResetDefaultColors;
 use crate::types::Vec2;
 use crate::{constants::*, crossterm_commands::SetCellPixelsColor};
-use palette::{Hsv, RgbHue, SetHue};
-use std::io::{self, Write, stdout};
+use palette::Hsv;
+use std::io::{self, Write};
 
 use crossterm::{
     QueueableCommand,
@@ -15,7 +16,8 @@ pub struct HuePicker {
     pub pos: Vec2,
     pub width: u32,
     pub height: u32,
-    pub hue_step: f32,
+    /// Hue at the top edge and degrees shown down to the bottom edge
+    pub hue_range: (f32, f32),
 }
 
 impl HuePicker {
@@ -24,44 +26,73 @@ impl HuePicker {
             width,
             height,
             pos,
-            hue_step: 360.0 / (height * 2) as f32,
+            hue_range: (0.0, 360.0),
             buf: Vec::with_capacity(height as usize * width as usize * 8),
         }
     }
 
-    pub fn draw(&mut self, fade: bool) -> io::Result<()> {
-        self.buf.clear();
+    /// Hue of a pixel row, two per cell
+    fn hue(&self, y: u32) -> f32 {
+        let (start, span) = self.hue_range;
+        (start + span * y as f32 / (self.height * 2) as f32).rem_euclid(360.0)
+    }
 
-        let mut pixel = Hsv::new(0.0, 1.0, 1.0);
+    pub fn draw(
+        &mut self,
+        out: &mut impl Write,
+        settings: &ColorSettings,
+        fade: bool,
+    ) -> io::Result<()> {
+        self.buf.clear();
         self.buf
             .queue(MoveTo(self.pos.x as u16, self.pos.y as u16))?;
 
-        for _ in 0..self.height {
-            let lower = Hsv::new(
-                (pixel.hue.into_positive_degrees() + self.hue_step) % 360.0,
-                1.0,
-                1.0,
-            );
-            self.buf.queue(SetCellPixelsColor(&pixel, &lower, fade))?;
+        for y in 0..self.height {
+            let upper = Hsv::new(self.hue(y * 2), 1.0, 1.0);
+            let lower = Hsv::new(self.hue(y * 2 + 1), 1.0, 1.0);
+            self.buf.queue(SetCellPixelsColor(
+                &settings.srgb(&upper),
+                &settings.srgb(&lower),
+                fade,
+            ))?;
             self.buf.queue(Print(
                 format!("{}", LOWER_HALF_BLOCK).repeat(self.width as usize),
             ))?;
-            pixel.set_hue(RgbHue::from_degrees(
-                (pixel.hue.into_positive_degrees() + self.hue_step * 2.0) % 360.0,
-            ));
             self.buf.queue(MoveLeft(self.width as u16))?;
             self.buf.queue(MoveDown(1))?;
         }
         self.buf.queue(ResetDefaultColors(fade))?;
-        stdout().write_all(&self.buf)?;
-        stdout().flush()?;
+        out.write_all(&self.buf)?;
         Ok(())
     }
 
+    /// Hue under a cell column and a pixel row, two per cell
+    #[allow(clippy::result_unit_err)]
     pub fn get(&self, x: u32, y: u32) -> Result<f32, ()> {
-        if x >= self.width || y >= self.height {
+        if x >= self.width || y >= self.height * 2 {
             return Err(());
         }
-        Ok(self.hue_step * (y * 2) as f32)
+        Ok(self.hue(y))
     }
+
+    /// Shows `360 / factor` degrees around `hue`, or all of them with a
+    /// factor of 1
+    pub fn zoom(&mut self, hue: f32, factor: f32) {
+        let factor = factor.max(1.0);
+        let span = 360.0 / factor;
+        let start = if factor == 1.0 { 0.0 } else { hue - span / 2.0 };
+        self.hue_range = (start.rem_euclid(360.0), span);
+    }
+}
+
+#[test]
+fn test_hue_picker_pixels() {
+    let mut picker = HuePicker::new(Vec2::zero(), 6, 30);
+    assert_eq!(picker.get(0, 0), Ok(0.0));
+    // The lower half of a cell is halfway to the next one
+    assert_eq!(picker.get(0, 1), Ok(6.0));
+    assert!(picker.get(0, 60).is_err());
+    picker.zoom(2.0, 10.0);
+    assert_eq!(picker.get(0, 0), Ok(344.0));
+    assert_eq!(picker.get(0, 30), Ok(2.0));
 }
@@ -0,0 +1,201 @@
+use crate::constants::*;
+use crate::crossterm_commands::*;
+use crate::image_file::{average_pixels, load_image};
+use crate::types::Vec2;
+use crate::utils::hsv_from_rgb;
+use image::RgbImage;
+use palette::Hsv;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crossterm::{
+    QueueableCommand,
+    cursor::{MoveDown, MoveLeft, MoveTo},
+    style::Print,
+};
+
+/// Displays an image with two pixels per cell to sample colors from it. The
+/// image is downscaled with nearest neighbor sampling, clicks sample the
+/// source pixel.
+pub struct ImageView {
+    pub buf: Vec<u8>,
+    pub pos: Vec2,
+    pub width: u32,
+    pub height: u32,
+    pub image: Option<RgbImage>,
+    pub path: Option<PathBuf>,
+    /// Source pixels per displayed pixel
+    pub scale: f32,
+    /// Source pixel displayed at the top left corner
+    pub pan: (f32, f32),
+    /// Clicks average the pixels within this distance of the sampled one
+    pub sample_radius: u32,
+}
+
+impl ImageView {
+    pub fn new(pos: Vec2, width: u32, height: u32) -> Self {
+        ImageView {
+            pos,
+            width,
+            height,
+            image: None,
+            path: None,
+            scale: 1.0,
+            pan: (0.0, 0.0),
+            sample_radius: 0,
+            buf: Vec::with_capacity(height as usize * width as usize * 48),
+        }
+    }
+
+    pub fn load(&mut self, path: &Path) -> io::Result<()> {
+        self.image = Some(load_image(path)?);
+        self.path = Some(path.to_path_buf());
+        self.fit();
+        Ok(())
+    }
+
+    /// Scales the image to fit entirely in the view.
+    pub fn fit(&mut self) {
+        if let Some(image) = &self.image {
+            self.scale = (image.width() as f32 / self.width as f32)
+                .max(image.height() as f32 / (self.height * 2) as f32);
+        }
+        self.pan = (0.0, 0.0);
+    }
+
+    /// Multiplies the magnification by `factor`, keeping the center in place.
+    pub fn zoom(&mut self, factor: f32) {
+        let view_width = self.width as f32;
+        let view_height = (self.height * 2) as f32;
+        let center = (
+            self.pan.0 + view_width * self.scale / 2.0,
+            self.pan.1 + view_height * self.scale / 2.0,
+        );
+        self.scale = (self.scale / factor).clamp(IMAGE_VIEW_MIN_SCALE, IMAGE_VIEW_MAX_SCALE);
+        self.pan = (
+            center.0 - view_width * self.scale / 2.0,
+            center.1 - view_height * self.scale / 2.0,
+        );
+        self.clamp_pan();
+    }
+
+    /// Moves the view by a number of cells.
+    pub fn pan_by(&mut self, dx: i32, dy: i32) {
+        self.pan.0 += dx as f32 * self.scale;
+        self.pan.1 += (dy * 2) as f32 * self.scale;
+        self.clamp_pan();
+    }
+
+    fn clamp_pan(&mut self) {
+        let Some(image) = &self.image else {
+            return;
+        };
+        let max_x = image.width() as f32 - self.width as f32 * self.scale;
+        let max_y = image.height() as f32 - (self.height * 2) as f32 * self.scale;
+        self.pan = (
+            self.pan.0.min(max_x).max(0.0),
+            self.pan.1.min(max_y).max(0.0),
+        );
+    }
+
+    /// Source pixel shown at a displayed pixel, `y` counts half cells.
+    pub fn source_pixel(&self, x: u32, y: u32) -> Option<(u32, u32)> {
+        let image = self.image.as_ref()?;
+        let source_x = (self.pan.0 + (x as f32 + 0.5) * self.scale) as u32;
+        let source_y = (self.pan.1 + (y as f32 + 0.5) * self.scale) as u32;
+        if source_x >= image.width() || source_y >= image.height() {
+            return None;
+        }
+        Some((source_x, source_y))
+    }
+
+    fn displayed_color(&self, x: u32, y: u32) -> Hsv {
+        match (self.image.as_ref(), self.source_pixel(x, y)) {
+            (Some(image), Some((source_x, source_y))) => {
+                let [r, g, b] = image.get_pixel(source_x, source_y).0;
+                hsv_from_rgb(r, g, b)
+            }
+            _ => hsv_from_rgb(BACKGROUND_COLOR.r, BACKGROUND_COLOR.g, BACKGROUND_COLOR.b),
+        }
+    }
+
+    pub fn draw(&mut self, out: &mut impl Write, fade: bool) -> io::Result<()> {
+        self.buf.clear();
+        self.buf.queue(ResetDefaultColors(fade))?;
+        if self.image.is_none() {
+            self.buf
+                .queue(FillRect(&self.pos, self.width as u16, self.height as u16))?;
+            self.buf
+                .queue(MoveTo(self.pos.x as u16, self.pos.y as u16))?;
+            self.buf.queue(Print("No image loaded, use :open <path>"))?;
+        } else {
+            self.buf
+                .queue(MoveTo(self.pos.x as u16, self.pos.y as u16))?;
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let top = self.displayed_color(x, y * 2);
+                    let bottom = self.displayed_color(x, y * 2 + 1);
+                    self.buf.queue(SetCellPixelsColor(&top, &bottom, fade))?;
+                    self.buf.queue(Print(LOWER_HALF_BLOCK))?;
+                }
+                self.buf.queue(MoveLeft(self.width as u16))?;
+                self.buf.queue(MoveDown(1))?;
+            }
+            self.buf.queue(ResetDefaultColors(fade))?;
+        }
+        out.write_all(&self.buf)?;
+        Ok(())
+    }
+
+    /// Samples the color at a cell column and a pixel row, two per cell,
+    /// averaged over `sample_radius`.
+    fn sample(&self, x: u32, y: u32) -> Option<(u8, u8, u8)> {
+        if x >= self.width || y >= self.height * 2 {
+            return None;
+        }
+        let image = self.image.as_ref()?;
+        let (source_x, source_y) = self.source_pixel(x, y)?;
+        Some(average_pixels(
+            image,
+            source_x,
+            source_y,
+            self.sample_radius,
+        ))
+    }
+
+    /// Samples the color under a cell column and a pixel row, two per cell
+    #[allow(clippy::result_unit_err)]
+    pub fn get(&self, x: u32, y: u32) -> Result<Hsv, ()> {
+        let (r, g, b) = self.sample(x, y).ok_or(())?;
+        Ok(hsv_from_rgb(r, g, b))
+    }
+
+    /// Samples both pixels of a cell, for clicks that are not reported in
+    /// pixels. A pixel past the bottom of the image is left out.
+    #[allow(clippy::result_unit_err)]
+    pub fn get_cell(&self, x: u32, y: u32) -> Result<Hsv, ()> {
+        let top = self.sample(x, y * 2).ok_or(())?;
+        let Some(bottom) = self.sample(x, y * 2 + 1) else {
+            return Ok(hsv_from_rgb(top.0, top.1, top.2));
+        };
+        let mean = |a: u8, b: u8| ((a as u16 + b as u16).div_ceil(2)) as u8;
+        Ok(hsv_from_rgb(
+            mean(top.0, bottom.0),
+            mean(top.1, bottom.1),
+            mean(top.2, bottom.2),
+        ))
+    }
+}
+
+#[test]
+fn test_image_view_halves() {
+    let mut view = ImageView::new(Vec2::zero(), 1, 1);
+    view.image = Some(RgbImage::from_fn(1, 2, |_, y| {
+        image::Rgb([if y == 0 { 255 } else { 0 }, 0, 0])
+    }));
+    view.fit();
+    assert_eq!(view.get(0, 0), Ok(hsv_from_rgb(255, 0, 0)));
+    assert_eq!(view.get(0, 1), Ok(hsv_from_rgb(0, 0, 0)));
+    assert_eq!(view.get(0, 2), Err(()));
+    assert_eq!(view.get_cell(0, 0), Ok(hsv_from_rgb(128, 0, 0)));
+}
@@ -1,16 +1,18 @@
-use crate::crossterm_commands::{PrintBold, ResetDefaultColors};
+use crate::color_space::ColorSettings;
+use crate::crossterm_commands::{PrintBold, PrintBoldColored, ResetDefaultColors};
 use crate::types::Vec2;
-use crate::utils::rgb_from_hsv;
+use crate::ui::line_editor::LineEditor;
+use crate::utils::{cmyk_from_hsv, format_decimal, rgb_from_hsv};
 use crate::{constants::*, crossterm_commands::SetForegroundColorWithFade};
 use palette::Hsv;
-use std::io::{self, Write, stdout};
+use std::io::{self, Write};
 
 use crossterm::{
     QueueableCommand,
     cursor::{Hide, MoveDown, MoveLeft, MoveTo, Show},
-    event::KeyCode,
-    execute,
-    style::Print,
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    queue,
+    style::{Attribute, Print, SetAttribute},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,10 +24,62 @@ pub enum Focus {
     H,
     S,
     V,
+    C,
+    M,
+    Y,
+    K,
+    LabL,
+    LabA,
+    LabB,
+    LchC,
+    LchH,
+    XyzX,
+    XyzY,
+    XyzZ,
     NONE,
 }
 
 impl Focus {
+    pub const FIELDS: [Focus; 19] = [
+        Focus::Hex,
+        Focus::R,
+        Focus::G,
+        Focus::B,
+        Focus::H,
+        Focus::S,
+        Focus::V,
+        Focus::C,
+        Focus::M,
+        Focus::Y,
+        Focus::K,
+        Focus::LchC,
+        Focus::LchH,
+        Focus::LabL,
+        Focus::LabA,
+        Focus::LabB,
+        Focus::XyzX,
+        Focus::XyzY,
+        Focus::XyzZ,
+    ];
+
+    /// Field after this one for Tab, going through the columns top to bottom.
+    /// The last field goes back to no focus and no focus to the first field.
+    pub fn next(&self) -> Focus {
+        match Focus::FIELDS.iter().position(|field| field == self) {
+            Some(index) => Focus::FIELDS.get(index + 1).copied().unwrap_or(Focus::NONE),
+            None => Focus::FIELDS[0],
+        }
+    }
+
+    /// Field before this one for Shift-Tab
+    pub fn previous(&self) -> Focus {
+        match Focus::FIELDS.iter().position(|field| field == self) {
+            Some(0) => Focus::NONE,
+            Some(index) => Focus::FIELDS[index - 1],
+            None => Focus::FIELDS[Focus::FIELDS.len() - 1],
+        }
+    }
+
     pub fn value(&self) -> u32 {
         match self {
             Focus::Hex => INPUTS_CB_HEIGHT,
@@ -35,10 +89,71 @@ impl Focus {
             Focus::H => INPUTS_CB_HEIGHT + 6,
             Focus::S => INPUTS_CB_HEIGHT + 7,
             Focus::V => INPUTS_CB_HEIGHT + 8,
+            Focus::C => INPUTS_CB_HEIGHT + 2,
+            Focus::M => INPUTS_CB_HEIGHT + 3,
+            Focus::Y => INPUTS_CB_HEIGHT + 4,
+            Focus::K => INPUTS_CB_HEIGHT + 5,
+            Focus::LabL => INPUTS_CB_HEIGHT + 2,
+            Focus::LabA => INPUTS_CB_HEIGHT + 3,
+            Focus::LabB => INPUTS_CB_HEIGHT + 4,
+            Focus::LchC => INPUTS_CB_HEIGHT + 7,
+            Focus::LchH => INPUTS_CB_HEIGHT + 8,
+            Focus::XyzX => INPUTS_CB_HEIGHT + 6,
+            Focus::XyzY => INPUTS_CB_HEIGHT + 7,
+            Focus::XyzZ => INPUTS_CB_HEIGHT + 8,
             Focus::NONE => 0,
         }
     }
 
+    pub fn column(&self) -> u32 {
+        match self {
+            Focus::C | Focus::M | Focus::Y | Focus::K | Focus::LchC | Focus::LchH => {
+                INPUTS_COLUMNS[1]
+            }
+            Focus::LabL | Focus::LabA | Focus::LabB | Focus::XyzX | Focus::XyzY | Focus::XyzZ => {
+                INPUTS_COLUMNS[2]
+            }
+            _ => INPUTS_COLUMNS[0],
+        }
+    }
+
+    /// Lab, LCh and XYZ values are decimal, relative to the white point
+    pub fn is_cie(&self) -> bool {
+        matches!(
+            self,
+            Focus::LabL
+                | Focus::LabA
+                | Focus::LabB
+                | Focus::LchC
+                | Focus::LchH
+                | Focus::XyzX
+                | Focus::XyzY
+                | Focus::XyzZ
+        )
+    }
+
+    /// CMYK and CIE values outside of the range are rejected rather than
+    /// clamped.
+    pub fn validates_range(&self) -> bool {
+        self.column() > 0
+    }
+
+    /// Every field but hex and RGB takes decimals
+    pub fn is_decimal(&self) -> bool {
+        !matches!(
+            self,
+            Focus::Hex | Focus::R | Focus::G | Focus::B | Focus::NONE
+        )
+    }
+
+    /// Fields that can be pasted with a `%` suffix
+    pub fn is_percentage(&self) -> bool {
+        matches!(
+            self,
+            Focus::S | Focus::V | Focus::C | Focus::M | Focus::Y | Focus::K
+        )
+    }
+
     pub fn prefix(&self) -> &'static str {
         match self {
             Focus::Hex => "#",
@@ -48,6 +163,43 @@ impl Focus {
             Focus::H => "H ",
             Focus::S => "S ",
             Focus::V => "V ",
+            Focus::C => "C ",
+            Focus::M => "M ",
+            Focus::Y => "Y ",
+            Focus::K => "K ",
+            Focus::LabL => "L ",
+            Focus::LabA => "a ",
+            Focus::LabB => "b ",
+            Focus::LchC => "Ch ",
+            Focus::LchH => "h  ",
+            Focus::XyzX => "X   ",
+            Focus::XyzY => "Lum ",
+            Focus::XyzZ => "Z   ",
+            Focus::NONE => "",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Focus::Hex => "hex",
+            Focus::R => "red",
+            Focus::G => "green",
+            Focus::B => "blue",
+            Focus::H => "hue",
+            Focus::S => "saturation",
+            Focus::V => "value",
+            Focus::C => "cyan",
+            Focus::M => "magenta",
+            Focus::Y => "yellow",
+            Focus::K => "black",
+            Focus::LabL => "lightness",
+            Focus::LabA => "a*",
+            Focus::LabB => "b*",
+            Focus::LchC => "chroma",
+            Focus::LchH => "LCh hue",
+            Focus::XyzX => "X",
+            Focus::XyzY => "Y",
+            Focus::XyzZ => "Z",
             Focus::NONE => "",
         }
     }
@@ -58,33 +210,139 @@ impl Focus {
             Focus::R => 3,
             Focus::G => 3,
             Focus::B => 3,
-            Focus::H => 3,
-            Focus::S => 3,
-            Focus::V => 3,
+            Focus::H => 6,
+            Focus::S => 6,
+            Focus::V => 6,
+            // Leaves a space before the Lab column
+            Focus::C | Focus::M | Focus::Y | Focus::K => 5,
+            Focus::LabA | Focus::LabB => 6,
+            Focus::LabL | Focus::LchC | Focus::LchH => 5,
+            Focus::XyzX | Focus::XyzY | Focus::XyzZ => 5,
             Focus::NONE => 0,
         }
     }
 
-    pub fn max_value(&self) -> u32 {
+    pub fn min_value(&self) -> f32 {
         match self {
-            Focus::Hex => 0xFFFFFF,
-            Focus::R => 255,
-            Focus::G => 255,
-            Focus::B => 255,
-            Focus::H => 360,
-            Focus::S => 100,
-            Focus::V => 100,
-            Focus::NONE => 0,
+            Focus::LabA | Focus::LabB => -128.0,
+            _ => 0.0,
+        }
+    }
+
+    pub fn max_value(&self) -> f32 {
+        match self {
+            Focus::Hex => 0xFFFFFF as f32,
+            Focus::R => 255.0,
+            Focus::G => 255.0,
+            Focus::B => 255.0,
+            Focus::H => 360.0,
+            Focus::S => 100.0,
+            Focus::V => 100.0,
+            Focus::C | Focus::M | Focus::Y | Focus::K => 100.0,
+            Focus::LabL => 100.0,
+            Focus::LabA | Focus::LabB => 127.0,
+            Focus::LchC => 150.0,
+            Focus::LchH => 360.0,
+            // The Z of the D65 white is 108.9
+            Focus::XyzX | Focus::XyzY | Focus::XyzZ => 110.0,
+            Focus::NONE => 0.0,
+        }
+    }
+
+    /// `value` moved by `delta`: hues go around the circle, the other values
+    /// stay within their range
+    pub fn step(&self, value: f32, delta: f32) -> f32 {
+        match self {
+            Focus::H | Focus::LchH => (value + delta).rem_euclid(360.0),
+            _ => (value + delta).clamp(self.min_value(), self.max_value()),
+        }
+    }
+
+    /// Whether the character can be typed in the field
+    pub fn accepts(&self, c: char) -> bool {
+        match self {
+            Focus::Hex => c.is_ascii_hexdigit(),
+            _ if self.is_decimal() => {
+                c.is_ascii_digit() || c == '.' || (c == '-' && self.min_value() < 0.0)
+            }
+            _ => c.is_ascii_digit(),
         }
     }
+
+    /// Value of the field for the color in the working space. Hex and CMYK
+    /// are sRGB, CIE values are relative to the white point and XYZ is scaled
+    /// so that Y is 100 for white.
+    pub fn read(&self, color: &Hsv, settings: &ColorSettings) -> f32 {
+        let srgb = settings.srgb(color);
+        let hex = rgb_from_hsv(&srgb);
+        let (r, g, b) = rgb_from_hsv(color);
+        let (c, m, y, k) = cmyk_from_hsv(&srgb);
+        let lab = settings.lab(color);
+        let lch = settings.lch(color);
+        let xyz = settings.xyz(color);
+        match self {
+            Focus::Hex => ((hex.0 as u32) << 16 | (hex.1 as u32) << 8 | hex.2 as u32) as f32,
+            Focus::R => r as f32,
+            Focus::G => g as f32,
+            Focus::B => b as f32,
+            Focus::H => color.hue.into_positive_degrees(),
+            Focus::S => color.saturation * 100.0,
+            Focus::V => color.value * 100.0,
+            Focus::C => c * 100.0,
+            Focus::M => m * 100.0,
+            Focus::Y => y * 100.0,
+            Focus::K => k * 100.0,
+            Focus::LabL => lab.0,
+            Focus::LabA => lab.1,
+            Focus::LabB => lab.2,
+            Focus::LchC => lch.1,
+            Focus::LchH => lch.2,
+            Focus::XyzX => xyz.0 * 100.0,
+            Focus::XyzY => xyz.1 * 100.0,
+            Focus::XyzZ => xyz.2 * 100.0,
+            Focus::NONE => 0.0,
+        }
+    }
+}
+
+/// Shown under the hex value when the color has to be gamut mapped for it
+const OUT_OF_SRGB_WARNING: &str = "\u{25b2} outside of sRGB";
+
+/// Shortest text of a value, without the float noise of the nudges
+fn format_number(value: f32) -> String {
+    format_decimal(value, INPUTS_DECIMALS)
+}
+
+/// Rounded value of an unfocused field, padded to clear what was typed in it
+fn pad_field(field: Focus, value: f32) -> String {
+    let text = match field {
+        Focus::XyzX | Focus::XyzY | Focus::XyzZ => format!("{:>5.1}", value),
+        _ => format!("{:>3.0}", value),
+    };
+    format!("{:<1$}", text, field.input_max_len())
+}
+
+/// What a key did in the focused field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldEvent {
+    /// Validated with enter, the value is `Err` if it could not be parsed
+    Validated(Focus, Result<f32, ()>),
+    /// The text changed, the value is `None` while it is not a complete value
+    Edited(Focus, Option<f32>),
+    /// Escape left the field without validating it
+    Cancelled,
 }
 
 pub struct Inputs {
     pub buf: Vec<u8>,
     pub pos: Vec2,
-    input_str: String,
+    editor: LineEditor,
     pub focus: Focus,
     pub modified: bool,
+    /// Added or removed by Up and Down
+    pub step: f32,
+    /// Added or removed by PageUp and PageDown
+    pub big_step: f32,
 }
 
 impl Inputs {
@@ -93,17 +351,27 @@ impl Inputs {
             pos,
             focus: Focus::NONE,
             buf: Vec::with_capacity(128 * 8),
-            input_str: String::with_capacity(8),
+            editor: LineEditor::default(),
             modified: false,
+            step: INPUTS_DEFAULT_STEP,
+            big_step: INPUTS_DEFAULT_BIG_STEP,
         }
     }
 
-    pub fn draw(&mut self, color: &Hsv, fade: bool) -> io::Result<()> {
+    pub fn draw(
+        &mut self,
+        out: &mut impl Write,
+        color: &Hsv,
+        settings: &ColorSettings,
+        fade: bool,
+    ) -> io::Result<()> {
         self.buf.clear();
-        let (r, g, b) = rgb_from_hsv(&color);
+        let srgb = settings.srgb(color);
+        let hex = rgb_from_hsv(&srgb);
+        let (r, g, b) = rgb_from_hsv(color);
         self.buf
             .queue(MoveTo(self.pos.x as u16, self.pos.y as u16))?;
-        self.buf.queue(SetForegroundColorWithFade(color, fade))?;
+        self.buf.queue(SetForegroundColorWithFade(&srgb, fade))?;
 
         // Draw color block
         for _ in 0..INPUTS_CB_HEIGHT {
@@ -118,9 +386,25 @@ impl Inputs {
 
         // Draw values
         self.buf
-            .queue(Print(format!("#{:02x}{:02x}{:02x}", r, g, b)))?;
+            .queue(Print(format!("#{:02x}{:02x}{:02x}", hex.0, hex.1, hex.2)))?;
         self.buf.queue(MoveLeft(7))?;
-        self.buf.queue(MoveDown(2))?;
+        self.buf.queue(MoveDown(1))?;
+        // The hex value and copies in sRGB notations are gamut mapped
+        if settings.in_srgb(color) {
+            self.buf
+                .queue(Print(" ".repeat(OUT_OF_SRGB_WARNING.chars().count())))?;
+        } else if fade {
+            self.buf.queue(Print(OUT_OF_SRGB_WARNING))?;
+        } else {
+            let CustomRgb { r, g, b } = ERROR_COLOR;
+            self.buf
+                .queue(PrintBoldColored(OUT_OF_SRGB_WARNING, r, g, b))?;
+            self.buf.queue(ResetDefaultColors(fade))?;
+        }
+        self.buf.queue(MoveTo(
+            self.pos.x as u16,
+            (self.pos.y + Focus::R.value()) as u16,
+        ))?;
 
         self.buf.queue(PrintBold("R "))?;
         self.buf.queue(Print(format!("{:>3}", r)))?;
@@ -138,196 +422,345 @@ impl Inputs {
         self.buf.queue(MoveDown(2))?;
 
         self.buf.queue(PrintBold("H "))?;
-        self.buf
-            .queue(Print(format!("{:>3.0}", color.hue.into_positive_degrees())))?;
-        self.buf.queue(MoveLeft(5))?;
+        self.buf.queue(Print(pad_field(
+            Focus::H,
+            color.hue.into_positive_degrees(),
+        )))?;
+        self.buf.queue(MoveLeft(8))?;
         self.buf.queue(MoveDown(1))?;
 
         self.buf.queue(PrintBold("S "))?;
         self.buf
-            .queue(Print(format!("{:>3.0}", color.saturation * 100.0)))?;
-        self.buf.queue(MoveLeft(5))?;
+            .queue(Print(pad_field(Focus::S, color.saturation * 100.0)))?;
+        self.buf.queue(MoveLeft(8))?;
         self.buf.queue(MoveDown(1))?;
 
         self.buf.queue(PrintBold("V "))?;
         self.buf
-            .queue(Print(format!("{:>3.0}", color.value * 100.0)))?;
-        self.buf.queue(MoveLeft(5))?;
+            .queue(Print(pad_field(Focus::V, color.value * 100.0)))?;
+        self.buf.queue(MoveLeft(8))?;
         self.buf.queue(MoveDown(1))?;
 
-        stdout().write_all(&self.buf)?;
-        stdout().flush()?;
-        Ok(())
+        for field in &Focus::FIELDS[7..] {
+            let value = field.read(color, settings);
+            self.buf.queue(MoveTo(
+                (self.pos.x + field.column()) as u16,
+                (self.pos.y + field.value()) as u16,
+            ))?;
+            self.buf.queue(PrintBold(field.prefix()))?;
+            self.buf.queue(Print(pad_field(*field, value)))?;
+        }
+        self.buf.queue(MoveTo(
+            (self.pos.x + Focus::C.column()) as u16,
+            (self.pos.y + Focus::Hex.value()) as u16,
+        ))?;
+        self.buf
+            .queue(Print(format!("{:<8}", settings.space.name())))?;
+        self.buf.queue(MoveTo(
+            (self.pos.x + Focus::LabL.column()) as u16,
+            (self.pos.y + Focus::Hex.value()) as u16,
+        ))?;
+        self.buf.queue(Print(settings.white_point.name()))?;
+
+        out.write_all(&self.buf)
     }
 
-    pub fn mouse_click(&mut self, x: u32, y: u32) -> Result<(), ()> {
-        if x >= 7 || y < INPUTS_CB_HEIGHT || y >= Focus::V.value() + 1 {
-            // 7 is the length of "#RRGGBB"
-            let _ = self.lose_focus();
+    /// Field under a position relative to the panel
+    pub fn field_at(&self, x: u32, y: u32) -> Option<Focus> {
+        Focus::FIELDS.into_iter().find(|field| {
+            let width = (field.prefix().len() + field.input_max_len()) as u32;
+            field.value() == y && (field.column()..field.column() + width).contains(&x)
+        })
+    }
+
+    /// Focuses the clicked field. Returns the clicked column of its text.
+    #[allow(clippy::result_unit_err)]
+    pub fn mouse_click(&mut self, out: &mut impl Write, x: u32, y: u32) -> Result<usize, ()> {
+        let Some(field) = self.field_at(x, y) else {
+            let _ = self.lose_focus(out);
             return Err(());
-        }
-        self.focus = match y {
-            y if y == Focus::Hex.value() => Focus::Hex,
-            y if y == Focus::R.value() => Focus::R,
-            y if y == Focus::G.value() => Focus::G,
-            y if y == Focus::B.value() => Focus::B,
-            y if y == Focus::H.value() => Focus::H,
-            y if y == Focus::S.value() => Focus::S,
-            y if y == Focus::V.value() => Focus::V,
-            _ => {
-                return Err(());
-            }
         };
-        Ok(())
+        self.focus = field;
+        let text_start = field.column() + field.prefix().len() as u32;
+        Ok(x.saturating_sub(text_start) as usize)
+    }
+
+    /// Puts the cursor of the focused field at `column` of its text
+    pub fn place_cursor(&mut self, out: &mut impl Write, column: usize) -> io::Result<()> {
+        self.editor.move_to(column, false);
+        self.draw_input_str(out)
+    }
+
+    /// Value of the text of the focused field, `Err` if it does not parse or
+    /// is out of range
+    #[allow(clippy::result_unit_err)]
+    pub fn parse(&self) -> Result<f32, ()> {
+        let text = self.editor.text().trim();
+        let value = match self.focus {
+            Focus::NONE => return Err(()),
+            Focus::Hex => u32::from_str_radix(text, 16)
+                .map(|value| value as f32)
+                .map_err(|_| ())?,
+            _ => text.parse::<f32>().map_err(|_| ())?,
+        };
+        let range = self.focus.min_value()..=self.focus.max_value();
+        if self.focus.validates_range() && !range.contains(&value) {
+            return Err(());
+        }
+        Ok(value)
     }
 
-    pub fn value_input(&mut self, input: KeyCode) -> Option<(Focus, u32)> {
+    /// Edits the focused field. Returns what happened to it, `None` if the
+    /// key did not change its text.
+    pub fn value_input(&mut self, out: &mut impl Write, event: KeyEvent) -> Option<FieldEvent> {
         if self.focus == Focus::NONE {
             return None;
         }
+        let input = event.code;
         if input.is_enter() {
             let focus = self.focus;
+            let value = self.parse();
             self.modified = false;
-            let _ = self.lose_focus();
-            let value = match focus {
-                Focus::Hex => u32::from_str_radix(&self.input_str, 16).unwrap_or(0),
-                _ => self.input_str.parse::<u32>().unwrap_or(0),
-            };
-            return Some((focus, value));
+            let _ = self.lose_focus(out);
+            return Some(FieldEvent::Validated(focus, value));
         }
 
         if input.is_esc() {
-            self.focus = Focus::NONE;
-            return None;
+            let _ = self.lose_focus(out);
+            return Some(FieldEvent::Cancelled);
         }
 
-        if input.is_backspace() {
-            self.modified = true;
-            if self.input_str.len() > 0 {
-                self.input_str.pop();
+        let select = event.modifiers.contains(KeyModifiers::SHIFT);
+        let editor = &mut self.editor;
+        let edited = match input {
+            KeyCode::Backspace => {
+                editor.backspace();
+                true
+            }
+            KeyCode::Delete => {
+                editor.delete();
+                true
+            }
+            KeyCode::Char('w') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                editor.delete_word();
+                true
+            }
+            KeyCode::Char('u') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                editor.delete_to_start();
+                true
+            }
+            KeyCode::Char(c) if !event.modifiers.contains(KeyModifiers::CONTROL) => {
+                let accepted =
+                    editor.len_with("x") <= self.focus.input_max_len() && self.focus.accepts(c);
+                if accepted {
+                    editor.insert(&c.to_ascii_lowercase().to_string());
+                }
+                accepted
             }
+            KeyCode::Left => {
+                editor.left(select);
+                false
+            }
+            KeyCode::Right => {
+                editor.right(select);
+                false
+            }
+            KeyCode::Home => {
+                editor.move_to(0, select);
+                false
+            }
+            KeyCode::End => {
+                editor.move_to(usize::MAX, select);
+                false
+            }
+            _ => false,
+        };
+        self.modified |= edited;
+
+        let nudged = match input {
+            KeyCode::Up => self.nudge(self.step),
+            KeyCode::Down => self.nudge(-self.step),
+            KeyCode::PageUp => self.nudge(self.big_step),
+            KeyCode::PageDown => self.nudge(-self.big_step),
+            _ => false,
+        };
+
+        let _ = self.draw_input_str(out);
+        if !edited && !nudged {
+            return None;
         }
+        Some(FieldEvent::Edited(self.focus, self.preview_value()))
+    }
 
-        if self.focus == Focus::Hex {
-            let c = input.as_char();
-            c.map(|c| {
-                if !self.modified {
-                    self.input_str.clear();
-                    self.modified = true;
-                }
-                if self.input_str.len() < self.focus.input_max_len() && c.is_digit(16) {
-                    self.input_str.push(c.to_ascii_lowercase());
-                }
-            });
-        } else {
-            let c = input.as_char();
-            c.map(|c| {
-                if !self.modified {
-                    self.input_str.clear();
-                    self.modified = true;
-                }
-                if self.input_str.len() < self.focus.input_max_len() && c.is_digit(10) {
-                    self.input_str.push(c);
-                }
-            });
-            match input {
-                KeyCode::Up => {
-                    let current_value = self.input_str.trim().parse::<u32>().unwrap_or(0);
-                    if current_value < self.focus.max_value() {
-                        self.input_str = format!("{}", current_value + 1);
-                    }
-                }
-                KeyCode::Down => {
-                    let current_value = self.input_str.trim().parse::<u32>().unwrap_or(0);
-                    if current_value > 0 {
-                        self.input_str = format!("{}", current_value - 1);
-                    }
-                }
-                KeyCode::Left => {
-                    let current_value = self.input_str.trim().parse::<u32>().unwrap_or(0);
-                    if current_value >= 10 {
-                        self.input_str = format!("{}", current_value - 10);
-                    } else {
-                        self.input_str = "0".to_string();
+    /// Adds `delta` to the value of the focused field, see `Focus::step`.
+    /// Returns false for the hex field, which has no steps.
+    pub fn nudge(&mut self, delta: f32) -> bool {
+        if matches!(self.focus, Focus::Hex | Focus::NONE) {
+            return false;
+        }
+        let current_value = self.editor.text().trim().parse::<f32>().unwrap_or(0.0);
+        self.editor
+            .set(format_number(self.focus.step(current_value, delta)));
+        true
+    }
+
+    /// Value of the text being typed in the focused field, if it is complete
+    pub fn preview_value(&self) -> Option<f32> {
+        // A hex value is only previewed once complete, "f" would be black
+        if self.focus == Focus::Hex && self.editor.text().len() != 6 {
+            return None;
+        }
+        self.parse().ok()
+    }
+
+    /// Inserts pasted text into the focused field. Returns false, leaving the
+    /// field untouched, if the text is not a valid value for the field.
+    pub fn paste(&mut self, out: &mut impl Write, text: &str) -> bool {
+        let mut text = text.trim();
+        let valid = match self.focus {
+            Focus::NONE => return false,
+            Focus::Hex => {
+                for prefix in ["#", "0x", "0X", "x", "X"] {
+                    if let Some(stripped) = text.strip_prefix(prefix) {
+                        text = stripped;
+                        break;
                     }
                 }
-                KeyCode::Right => {
-                    let current_value = self.input_str.trim().parse::<u32>().unwrap_or(0);
-                    if current_value <= self.focus.max_value() - 10 {
-                        self.input_str = format!("{}", current_value + 10);
-                    }
+                text.chars().all(|c| c.is_ascii_hexdigit())
+            }
+            focus => {
+                if focus.is_percentage() {
+                    text = text.strip_suffix('%').unwrap_or(text).trim_end();
                 }
-                _ => {}
+                text.chars().all(|c| focus.accepts(c))
             }
+        };
+        if text.is_empty() || !valid || self.editor.len_with(text) > self.focus.input_max_len() {
+            return false;
         }
+        self.editor.insert(&text.to_ascii_lowercase());
+        self.modified = true;
+        let _ = self.draw_input_str(out);
+        true
+    }
 
-        let _ = execute!(
-            stdout(),
-            MoveTo(
-                self.pos.x as u16 + self.focus.prefix().len() as u16,
-                self.pos.y as u16 + self.focus.value() as u16
-            ),
+    /// Redraws the text of the focused field, with its selection in reverse
+    /// video, and puts the cursor in it
+    pub fn draw_input_str(&self, out: &mut impl Write) -> io::Result<()> {
+        let x = (self.pos.x + self.focus.column()) as u16 + self.focus.prefix().len() as u16;
+        let y = self.pos.y as u16 + self.focus.value() as u16;
+        let text = self.editor.text();
+        let selection = self.editor.selection().unwrap_or(0..0);
+        queue!(
+            out,
+            MoveTo(x, y),
             Show,
-            Print(format!("{}", " ".repeat(7))), //Equivalent of clearing
-            MoveLeft(7),
-            Print(&self.input_str),
-        );
-        let _ = stdout().flush();
-        return None;
+            Print(" ".repeat(self.focus.input_max_len())), //Equivalent of clearing
+            MoveTo(x, y),
+            Print(&text[..selection.start]),
+            SetAttribute(Attribute::Reverse),
+            Print(&text[selection.clone()]),
+            SetAttribute(Attribute::NoReverse),
+            Print(&text[selection.end..]),
+            MoveTo(x + self.editor.cursor() as u16, y),
+        )
     }
 
-    pub fn gain_focus(&mut self, color: &Hsv) -> io::Result<()> {
+    pub fn gain_focus(
+        &mut self,
+        out: &mut impl Write,
+        color: &Hsv,
+        settings: &ColorSettings,
+    ) -> io::Result<()> {
         if self.focus != Focus::NONE {
             // NOTE: not sure about this behavior
-            self.draw(color, false)?;
+            self.draw(out, color, settings, false)?;
         }
-        let (r, g, b) = rgb_from_hsv(&color);
-        match self.focus {
-            Focus::Hex => {
-                self.input_str = format!("{:02x}{:02x}{:02x}", r, g, b);
+        let (r, g, b) = rgb_from_hsv(color);
+        let text = match self.focus {
+            Focus::Hex => format!("{:06x}", self.focus.read(color, settings) as u32),
+            Focus::R => r.to_string(),
+            Focus::G => g.to_string(),
+            Focus::B => b.to_string(),
+            Focus::H | Focus::S | Focus::V | Focus::C | Focus::M | Focus::Y | Focus::K => {
+                format_number(self.focus.read(color, settings))
             }
-            Focus::R => {
-                self.input_str = format!("{:>3}", r);
+            Focus::LabL
+            | Focus::LabA
+            | Focus::LabB
+            | Focus::LchC
+            | Focus::LchH
+            | Focus::XyzX
+            | Focus::XyzY
+            | Focus::XyzZ => format!("{:.1}", self.focus.read(color, settings)),
+            Focus::NONE => {
+                self.editor.clear();
+                self.modified = false;
+                return Ok(());
             }
-            Focus::G => {
-                self.input_str = format!("{:>3}", g);
-            }
-            Focus::B => {
-                self.input_str = format!("{:>3}", b);
-            }
-            Focus::H => {
-                self.input_str = format!("{:>3.0}", color.hue.into_positive_degrees());
-            }
-            Focus::S => {
-                self.input_str = format!("{:>3.0}", color.saturation * 100.0);
-            }
-            Focus::V => {
-                self.input_str = format!("{:>3.0}", color.value * 100.0);
-            }
-            Focus::NONE => self.input_str.clear(),
         };
+        self.editor.set(text);
         self.modified = false;
-        execute!(
-            stdout(),
-            MoveTo(
-                self.pos.x as u16 + self.focus.prefix().len() as u16,
-                self.pos.y as u16 + self.focus.value() as u16
-            ),
-            Show,
-            Print(format!("{}", " ".repeat(7))), //Equivalent of clearing
-            MoveLeft(7),
-            Print(&self.input_str),
-            MoveLeft(self.focus.input_max_len() as u16 - self.input_str.len() as u16),
-        )?;
-        stdout().flush()?;
-        Ok(())
+        self.draw_input_str(out)
     }
 
     // Returns true if value was applied, false if lost focus without applying
-    pub fn lose_focus(&mut self) -> bool {
+    pub fn lose_focus(&mut self, out: &mut impl Write) -> bool {
         self.focus = Focus::NONE;
-        let _ = execute!(stdout(), Hide);
-        let _ = stdout().flush();
-        return !self.modified;
+        let _ = queue!(out, Hide);
+        !self.modified
     }
 }
+
+#[test]
+fn test_focus_cycle() {
+    assert_eq!(Focus::NONE.next(), Focus::Hex);
+    assert_eq!(Focus::V.next(), Focus::C);
+    assert_eq!(Focus::XyzZ.next(), Focus::NONE);
+    assert_eq!(Focus::NONE.previous(), Focus::XyzZ);
+    assert_eq!(Focus::Hex.previous(), Focus::NONE);
+    let mut focus = Focus::NONE;
+    for _ in 0..=Focus::FIELDS.len() {
+        focus = focus.next();
+    }
+    assert_eq!(focus, Focus::NONE);
+}
+
+#[test]
+fn test_field_step() {
+    assert_eq!(Focus::H.step(359.5, 1.0), 0.5);
+    assert_eq!(Focus::LchH.step(0.5, -1.0), 359.5);
+    assert_eq!(Focus::S.step(99.5, 1.0), 100.0);
+    assert_eq!(Focus::LabA.step(-127.5, -1.0), -128.0);
+}
+
+#[test]
+fn test_paste() {
+    let mut inputs = Inputs::new(Vec2::zero());
+    let mut out = Vec::new();
+    let settings = ColorSettings::default();
+    inputs.focus = Focus::Hex;
+    inputs
+        .gain_focus(&mut out, &Hsv::new(0.0, 1.0, 1.0), &settings)
+        .unwrap();
+    // The selected text is replaced, the prefix and case are dropped
+    assert!(inputs.paste(&mut out, " #00FF00\n"));
+    assert_eq!(inputs.editor.text(), "00ff00");
+    assert_eq!(inputs.parse(), Ok(0x00ff00 as f32));
+    assert!(inputs.modified);
+
+    // Invalid and too long texts leave the field untouched
+    assert!(!inputs.paste(&mut out, "green"));
+    assert!(!inputs.paste(&mut out, ""));
+    // Typed in the middle of a field, the text would not fit
+    inputs.editor.set("00f".to_string());
+    inputs.editor.move_to(3, false);
+    assert!(!inputs.paste(&mut out, "#ff00ff"));
+    assert_eq!(inputs.editor.text(), "00f");
+
+    inputs.focus = Focus::S;
+    inputs.editor.clear();
+    assert!(inputs.paste(&mut out, "50%"));
+    assert_eq!(inputs.parse(), Ok(50.0));
+    assert!(!inputs.paste(&mut out, "5e1"));
+}
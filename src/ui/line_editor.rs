@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+/// Single-line text with a cursor and a selection, for the input fields. The
+/// fields only hold ASCII so positions are byte indices.
+#[derive(Debug, Default, Clone)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize,
+    /// Other end of the selection, the cursor being one end
+    anchor: Option<usize>,
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replaces the text and selects all of it, so typing replaces it
+    pub fn set(&mut self, text: String) {
+        self.cursor = text.len();
+        self.anchor = Some(0);
+        self.text = text;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+        self.anchor = None;
+    }
+
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        match anchor.cmp(&self.cursor) {
+            std::cmp::Ordering::Less => Some(anchor..self.cursor),
+            std::cmp::Ordering::Greater => Some(self.cursor..anchor),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Length of the text once `inserted` replaces the selection
+    pub fn len_with(&self, inserted: &str) -> usize {
+        let selected = self.selection().map_or(0, |range| range.len());
+        self.text.len() - selected + inserted.len()
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let Some(range) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        self.cursor = range.start;
+        self.text.replace_range(range, "");
+        self.anchor = None;
+        true
+    }
+
+    /// Inserts at the cursor, replacing the selection
+    pub fn insert(&mut self, inserted: &str) {
+        self.delete_selection();
+        self.text.insert_str(self.cursor, inserted);
+        self.cursor += inserted.len();
+    }
+
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() && self.cursor > 0 {
+            self.cursor -= 1;
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if !self.delete_selection() && self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    /// Ctrl-W: deletes the word before the cursor. Words are separated by
+    /// punctuation, so `-12.5` takes two.
+    pub fn delete_word(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        let before = &self.text[..self.cursor];
+        let trimmed = before.trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
+        let start = trimmed
+            .rfind(|c: char| !c.is_ascii_alphanumeric())
+            .map_or(0, |i| i + 1);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Ctrl-U: deletes everything before the cursor
+    pub fn delete_to_start(&mut self) {
+        self.anchor = None;
+        self.text.replace_range(..self.cursor, "");
+        self.cursor = 0;
+    }
+
+    /// Moves the cursor, extending the selection when `select` is set
+    pub fn move_to(&mut self, position: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = position.min(self.text.len());
+    }
+
+    /// Left arrow: collapses the selection to its start or moves back
+    pub fn left(&mut self, select: bool) {
+        match self.selection() {
+            Some(range) if !select => self.move_to(range.start, false),
+            _ => self.move_to(self.cursor.saturating_sub(1), select),
+        }
+    }
+
+    /// Right arrow: collapses the selection to its end or moves forward
+    pub fn right(&mut self, select: bool) {
+        match self.selection() {
+            Some(range) if !select => self.move_to(range.end, false),
+            _ => self.move_to(self.cursor + 1, select),
+        }
+    }
+}
+
+#[test]
+fn test_line_editor() {
+    let mut editor = LineEditor::default();
+    editor.set("ff5733".to_string());
+    // Typing replaces the selected text
+    editor.insert("1");
+    assert_eq!((editor.text(), editor.cursor()), ("1", 1));
+
+    // Fixing one digit in the middle
+    editor.set("ff5733".to_string());
+    editor.move_to(3, false);
+    editor.backspace();
+    editor.insert("8");
+    assert_eq!((editor.text(), editor.cursor()), ("ff8733", 3));
+    editor.delete();
+    assert_eq!(editor.text(), "ff833");
+    editor.move_to(0, true);
+    assert_eq!(editor.selection(), Some(0..3));
+    assert_eq!(editor.len_with("00"), 4);
+    editor.right(false);
+    assert_eq!((editor.selection(), editor.cursor()), (None, 3));
+
+    editor.set("-12.5".to_string());
+    editor.left(false);
+    assert_eq!(editor.cursor(), 0);
+    editor.move_to(5, false);
+    editor.delete_word();
+    assert_eq!(editor.text(), "-12.");
+    editor.delete_word();
+    assert_eq!(editor.text(), "-");
+    editor.insert("40");
+    editor.left(false);
+    editor.delete_to_start();
+    assert_eq!((editor.text(), editor.cursor()), ("0", 0));
+}
@@ -1,4 +1,10 @@
+pub mod ansi_preview;
+pub mod command_line;
 pub mod hue_picker;
+pub mod image_view;
 pub mod inputs;
+pub mod line_editor;
 pub mod saturation_value_picker;
+pub mod status_bar;
+pub mod swatch_strip;
 pub mod value_display;
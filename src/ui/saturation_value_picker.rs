@@ -1,7 +1,8 @@
+use crate::color_space::ColorSettings;
 use crate::constants::*;
 use crate::types::Vec2;
 use palette::{Hsv, RgbHue, SetHue};
-use std::io::{self, Write, stdout};
+use std::io::{self, Write};
 
 use crossterm::{
     QueueableCommand,
@@ -15,8 +16,10 @@ pub struct SaturationValuePicker {
     pub buf: Vec<u8>,
     pub pos: Vec2,
     pub selected_color: Hsv,
-    pub saturation_step: f64,
-    pub value_step: f64,
+    /// Saturations shown from the left to the right edge
+    pub saturation_range: (f32, f32),
+    /// Values shown from the bottom to the top edge
+    pub value_range: (f32, f32),
     pub width: u32,
     pub height: u32,
 }
@@ -27,57 +30,62 @@ impl SaturationValuePicker {
             width,
             height,
             pos,
-            saturation_step: if width == 1 {
-                0.0
-            } else {
-                1.0 / (width - 1) as f64
-            },
-            value_step: if height == 1 {
-                0.0
-            } else {
-                1.0 / (height - 1) as f64
-            },
+            saturation_range: (0.0, 1.0),
+            value_range: (0.0, 1.0),
             selected_color: Hsv::new(RgbHue::from_degrees(0.0), 1.0, 1.0),
             buf: Vec::with_capacity(height as usize * width as usize * 8),
         }
     }
 
-    pub fn draw(&mut self, fade: bool) -> io::Result<()> {
-        let mut pixel = Hsv::new(self.selected_color.hue.into_positive_degrees(), 0.0, 1.0);
+    /// Color of a pixel. Cells hold two pixels, so `y` goes up to twice the
+    /// height.
+    fn pixel(&self, x: u32, y: u32) -> Hsv {
+        let (min_saturation, max_saturation) = self.saturation_range;
+        let (min_value, max_value) = self.value_range;
+        let columns = self.width.saturating_sub(1).max(1) as f32;
+        let rows = (self.height * 2).saturating_sub(1).max(1) as f32;
+        Hsv::new(
+            self.selected_color.hue,
+            min_saturation + (max_saturation - min_saturation) * x as f32 / columns,
+            max_value - (max_value - min_value) * y as f32 / rows,
+        )
+    }
+
+    /// Draws the gradient of the working space, mapped to sRGB for the terminal
+    pub fn draw(
+        &mut self,
+        out: &mut impl Write,
+        settings: &ColorSettings,
+        fade: bool,
+    ) -> io::Result<()> {
         self.buf.clear();
         self.buf
             .queue(MoveTo(self.pos.x as u16, self.pos.y as u16))?;
-        for _ in 0..self.height {
-            for _ in 0..self.width {
-                let mut lower = pixel.clone();
-                lower.value = lower.value - (self.value_step as f32 / 2.0).max(0.0);
-                self.buf.queue(SetCellPixelsColor(&pixel, &lower, fade))?;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (upper, lower) = (self.pixel(x, y * 2), self.pixel(x, y * 2 + 1));
+                self.buf.queue(SetCellPixelsColor(
+                    &settings.srgb(&upper),
+                    &settings.srgb(&lower),
+                    fade,
+                ))?;
                 self.buf.queue(Print(LOWER_HALF_BLOCK))?;
-                pixel.saturation += self.saturation_step as f32;
             }
-            pixel = Hsv::new(
-                pixel.hue.into_positive_degrees(),
-                0.0,
-                pixel.value - self.value_step as f32,
-            );
             self.buf.queue(MoveLeft(self.width as u16))?;
             self.buf.queue(MoveDown(1))?;
         }
         self.buf.queue(ResetDefaultColors(fade))?;
-        stdout().write_all(&self.buf)?;
-        stdout().flush()?;
+        out.write_all(&self.buf)?;
         Ok(())
     }
 
+    /// Color under a cell column and a pixel row, two per cell
+    #[allow(clippy::result_unit_err)]
     pub fn get(&self, x: u32, y: u32) -> Result<Hsv, ()> {
-        if x >= self.width || y >= self.height {
+        if x >= self.width || y >= self.height * 2 {
             return Err(());
         }
-        Ok(Hsv::new(
-            RgbHue::from_degrees(self.selected_color.hue.into_positive_degrees()),
-            x as f32 * self.saturation_step as f32,
-            1.0 - y as f32 * (self.value_step) as f32,
-        ))
+        Ok(self.pixel(x, y))
     }
 
     pub fn set_hue(&mut self, hue_degrees: f32) {
@@ -85,11 +93,52 @@ impl SaturationValuePicker {
             .set_hue(RgbHue::from_degrees(hue_degrees));
     }
 
+    /// Adds to the saturation and value of the selected color, within 0 and 1
+    pub fn nudge(&mut self, saturation: f32, value: f32) {
+        let color = &mut self.selected_color;
+        color.saturation = (color.saturation + saturation).clamp(0.0, 1.0);
+        color.value = (color.value + value).clamp(0.0, 1.0);
+    }
+
+    /// Shows `1 / factor` of the saturations and values around the selected
+    /// color, or all of them with a factor of 1
+    pub fn zoom(&mut self, factor: f32) {
+        let span = 1.0 / factor.max(1.0);
+        let around = |center: f32| {
+            let min = (center - span / 2.0).clamp(0.0, 1.0 - span);
+            (min, min + span)
+        };
+        self.saturation_range = around(self.selected_color.saturation);
+        self.value_range = around(self.selected_color.value);
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.saturation_range != (0.0, 1.0) || self.value_range != (0.0, 1.0)
+    }
+
+    /// Selects the color under a cell column and a pixel row
+    #[allow(clippy::result_unit_err)]
     pub fn change_color(&mut self, x: u32, y: u32) -> Result<(), ()> {
-        if x >= self.width || y >= self.height {
-            return Err(());
-        }
         self.selected_color = self.get(x, y)?;
         Ok(())
     }
 }
+
+#[test]
+fn test_sv_picker_pixels() {
+    let mut picker = SaturationValuePicker::new(Vec2::zero(), 60, 30);
+    // Both pixels of the last row can be picked, down to black
+    let corner = picker.get(59, 59).unwrap();
+    assert_eq!((corner.saturation, corner.value), (1.0, 0.0));
+    assert!(picker.get(59, 58).unwrap().value > 0.0);
+    assert!(picker.get(0, 60).is_err());
+
+    picker.selected_color = Hsv::new(0.0, 0.99, 0.5);
+    picker.zoom(10.0);
+    assert!(picker.is_zoomed());
+    let corner = picker.get(59, 59).unwrap();
+    assert!((corner.saturation - 1.0).abs() < 1e-6);
+    assert!((corner.value - 0.45).abs() < 1e-6);
+    picker.zoom(1.0);
+    assert!(!picker.is_zoomed());
+}
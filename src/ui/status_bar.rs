@@ -0,0 +1,171 @@
+use crate::constants::*;
+use crate::crossterm_commands::{FillRect, PrintBoldColored, ResetDefaultColors};
+use crate::types::Vec2;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::{QueueableCommand, cursor::MoveTo, style::Print};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageLevel {
+    Info,
+    Success,
+    Error,
+}
+
+impl MessageLevel {
+    pub fn color(&self) -> CustomRgb {
+        match self {
+            MessageLevel::Info => TEXT_COLOR,
+            MessageLevel::Success => SUCCESS_COLOR,
+            MessageLevel::Error => ERROR_COLOR,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            MessageLevel::Error => STATUS_ERROR_DURATION,
+            _ => STATUS_MESSAGE_DURATION,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub level: MessageLevel,
+    pub text: String,
+}
+
+/// Single line at the bottom of the UI showing feedback messages. Messages
+/// are queued and each one is displayed until it expires, expiry is checked
+/// by `tick` which runs on every iteration of the poll loop.
+pub struct StatusBar {
+    pub buf: Vec<u8>,
+    pub pos: Vec2,
+    pub width: u32,
+    queue: VecDeque<Message>,
+    current: Option<(Message, Instant)>,
+}
+
+impl StatusBar {
+    pub fn new(pos: Vec2, width: u32) -> Self {
+        StatusBar {
+            pos,
+            width,
+            queue: VecDeque::new(),
+            current: None,
+            buf: Vec::with_capacity(width as usize * 4),
+        }
+    }
+
+    /// Queues a message. Only its first line is kept and control characters
+    /// are replaced, messages can quote pasted text or file names.
+    pub fn push(&mut self, level: MessageLevel, text: impl Into<String>) {
+        let text = text.into();
+        let text = text
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        self.queue.push_back(Message { level, text });
+    }
+
+    pub fn current(&self) -> Option<&Message> {
+        self.current.as_ref().map(|(message, _)| message)
+    }
+
+    /// Expires the displayed message and shows the next queued one. A message
+    /// is shortened to `STATUS_MIN_DURATION` when others are waiting. Returns
+    /// true if the status bar needs to be redrawn.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let mut changed = false;
+        if let Some((message, shown_at)) = &self.current {
+            let duration = if self.queue.is_empty() {
+                message.level.duration()
+            } else {
+                STATUS_MIN_DURATION
+            };
+            if now.duration_since(*shown_at) >= duration {
+                self.current = None;
+                changed = true;
+            }
+        }
+        if self.current.is_none()
+            && let Some(message) = self.queue.pop_front()
+        {
+            self.current = Some((message, now));
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn draw(&mut self, out: &mut impl Write, fade: bool) -> io::Result<()> {
+        self.buf.clear();
+        self.buf.queue(ResetDefaultColors(fade))?;
+        self.buf.queue(FillRect(&self.pos, self.width as u16, 1))?;
+        if let Some((message, _)) = &self.current {
+            let mut text: String = message.text.chars().take(self.width as usize).collect();
+            if message.text.chars().count() > self.width as usize {
+                text.pop();
+                text.push('\u{2026}');
+            }
+            let color = message.level.color();
+            self.buf
+                .queue(MoveTo(self.pos.x as u16, self.pos.y as u16))?;
+            if fade {
+                self.buf.queue(Print(text))?;
+            } else {
+                self.buf
+                    .queue(PrintBoldColored(&text, color.r, color.g, color.b))?;
+            }
+        }
+        out.write_all(&self.buf)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_status_bar_queue() {
+    let mut status_bar = StatusBar::new(Vec2::zero(), 10);
+    let start = Instant::now();
+    assert!(!status_bar.tick(start));
+
+    status_bar.push(MessageLevel::Success, "first");
+    status_bar.push(MessageLevel::Error, "second");
+    assert!(status_bar.tick(start));
+    assert_eq!(status_bar.current().unwrap().text, "first");
+
+    // Shortened because "second" is waiting
+    assert!(!status_bar.tick(start + STATUS_MIN_DURATION / 2));
+    assert!(status_bar.tick(start + STATUS_MIN_DURATION));
+    assert_eq!(status_bar.current().unwrap().level, MessageLevel::Error);
+
+    let shown_at = start + STATUS_MIN_DURATION;
+    assert!(!status_bar.tick(shown_at + STATUS_MESSAGE_DURATION));
+    assert!(status_bar.tick(shown_at + STATUS_ERROR_DURATION));
+    assert!(status_bar.current().is_none());
+}
+
+#[test]
+fn test_status_bar_control_characters() {
+    use crate::render::Screen;
+
+    let mut status_bar = StatusBar::new(Vec2::zero(), 10);
+    status_bar.push(MessageLevel::Error, "a\nb\x1b]0;x\x07");
+    status_bar.push(MessageLevel::Error, "c\x1b]0;x\x07\rd");
+    let start = Instant::now();
+    status_bar.tick(start);
+    let mut bytes = Vec::new();
+    status_bar.draw(&mut bytes, false).unwrap();
+    assert!(!bytes.contains(&b'\n') && !bytes.contains(&0x07));
+    let mut screen = Screen::new(10, 2);
+    screen.write_all(&bytes).unwrap();
+    assert_eq!(screen.row_text(0), "a");
+    assert_eq!(screen.row_text(1), "");
+
+    status_bar.tick(start + STATUS_ERROR_DURATION);
+    assert_eq!(status_bar.current().unwrap().text, "c ]0;x  d");
+}
@@ -0,0 +1,115 @@
+use crate::crossterm_commands::{PrintBold, ResetDefaultColors};
+use crate::types::Vec2;
+use crate::utils::{contrast_text_color, fade_color, rgb_from_hsv};
+use palette::Hsv;
+use std::io::{self, Write};
+
+use crossterm::{
+    QueueableCommand,
+    cursor::MoveTo,
+    style::{Color, Print, SetBackgroundColor, SetForegroundColor},
+};
+
+/// A titled grid of color swatches. The colors are owned by the caller and
+/// passed to `draw`, only the first `capacity` colors are shown.
+pub struct SwatchStrip {
+    pub buf: Vec<u8>,
+    pub pos: Vec2,
+    pub title: String,
+    pub swatch_width: u16,
+    pub columns: usize,
+    pub capacity: usize,
+    /// Label swatches with the number key selecting them: 1-9 then 0
+    pub numbered: bool,
+}
+
+impl SwatchStrip {
+    pub fn new(
+        pos: Vec2,
+        title: impl Into<String>,
+        swatch_width: u16,
+        columns: usize,
+        capacity: usize,
+        numbered: bool,
+    ) -> Self {
+        SwatchStrip {
+            pos,
+            title: title.into(),
+            swatch_width,
+            columns,
+            capacity,
+            numbered,
+            buf: Vec::with_capacity(capacity * swatch_width as usize * 32),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.capacity.div_ceil(self.columns)
+    }
+
+    pub fn draw(&mut self, out: &mut impl Write, colors: &[Hsv], fade: bool) -> io::Result<()> {
+        self.buf.clear();
+        self.buf.queue(ResetDefaultColors(fade))?;
+        self.buf
+            .queue(MoveTo(self.pos.x as u16, self.pos.y as u16))?;
+        let row_width = self.columns * self.swatch_width as usize;
+        let title: String = self.title.chars().take(row_width).collect();
+        self.buf
+            .queue(PrintBold(&format!("{:<width$}", title, width = row_width)))?;
+
+        for row in 0..self.rows() {
+            self.buf.queue(MoveTo(
+                self.pos.x as u16,
+                self.pos.y as u16 + 1 + row as u16,
+            ))?;
+            self.buf.queue(Print(" ".repeat(row_width)))?;
+        }
+
+        for (i, color) in colors.iter().take(self.capacity).enumerate() {
+            let color = if fade { fade_color(*color) } else { *color };
+            let (r, g, b) = rgb_from_hsv(&color);
+            let (fg_r, fg_g, fg_b) = contrast_text_color(&color);
+            let label = if self.numbered {
+                format!("{}", (i + 1) % 10)
+            } else {
+                String::new()
+            };
+            self.buf.queue(MoveTo(
+                self.pos.x as u16 + (i % self.columns) as u16 * self.swatch_width,
+                self.pos.y as u16 + 1 + (i / self.columns) as u16,
+            ))?;
+            self.buf.queue(SetBackgroundColor(Color::Rgb { r, g, b }))?;
+            self.buf.queue(SetForegroundColor(Color::Rgb {
+                r: fg_r,
+                g: fg_g,
+                b: fg_b,
+            }))?;
+            self.buf.queue(Print(format!(
+                "{:<width$}",
+                label,
+                width = self.swatch_width as usize
+            )))?;
+        }
+        self.buf.queue(ResetDefaultColors(fade))?;
+        out.write_all(&self.buf)?;
+        Ok(())
+    }
+
+    /// Returns the index of the swatch under the position, relative to the
+    /// strip. The index can be past the end of the displayed colors.
+    #[allow(clippy::result_unit_err)]
+    pub fn mouse_click(&self, x: u32, y: u32) -> Result<usize, ()> {
+        if y == 0 || y as usize > self.rows() {
+            return Err(());
+        }
+        let column = (x / self.swatch_width as u32) as usize;
+        if column >= self.columns {
+            return Err(());
+        }
+        let index = (y as usize - 1) * self.columns + column;
+        if index >= self.capacity {
+            return Err(());
+        }
+        Ok(index)
+    }
+}
@@ -1,28 +1,47 @@
+use crate::color_space::ColorSettings;
 use crate::constants::*;
 use crate::types::Vec2;
-use crate::utils::rgb_from_hsv;
+use crate::utils::{cmyk_from_hsv, rgb_from_hsv};
 use palette::Hsv;
-use std::io::{self, stdout};
+use std::io::{self, Write};
 
 use crate::crossterm_commands::*;
-use crossterm::{cursor::MoveTo, execute, style::Print, terminal::*};
+use crossterm::{cursor::MoveTo, queue, style::Print, terminal::*};
 
-pub fn draw_value_display(pos: &Vec2, color: &Hsv, fade: bool) -> io::Result<()> {
+/// Narrower than `SPACE` so that both lines fit in `TOTAL_WIDTH`
+const SEPARATOR: &str = "  ";
+
+/// Two lines: the color with its hex, RGB, HSV and CMYK values, then the CIE
+/// values relative to the white point under it. `color` is in the working
+/// space, which RGB and HSV are read in, hex and CMYK are sRGB.
+pub fn draw_value_display(
+    out: &mut impl Write,
+    pos: &Vec2,
+    color: &Hsv,
+    settings: &ColorSettings,
+    fade: bool,
+) -> io::Result<()> {
+    let srgb = settings.srgb(color);
+    let hex = rgb_from_hsv(&srgb);
     let (r, g, b) = rgb_from_hsv(color);
-    execute!(
-        stdout(),
+    let (c, m, y, k) = cmyk_from_hsv(&srgb);
+    let lab = settings.lab(color);
+    let lch = settings.lch(color);
+    let xyz = settings.xyz(color);
+    queue!(
+        out,
         MoveTo(pos.x as u16, pos.y as u16),
         Clear(ClearType::CurrentLine),
-        SetForegroundColorWithFade(color, fade),
+        SetForegroundColorWithFade(&srgb, fade),
         Print(format!("{}", FULL_CELL_BLOCK).repeat(8)),
         ResetDefaultColors(fade),
-        Print(SPACE),
+        Print(SEPARATOR),
         PrintBold("HEX: "),
-        Print(format!("#{:02X}{:02X}{:02X}", r, g, b)),
-        Print(SPACE),
+        Print(format!("#{:02X}{:02X}{:02X}", hex.0, hex.1, hex.2)),
+        Print(SEPARATOR),
         PrintBold("RGB: "),
         Print(format!("{:>3}, {:>3}, {:>3}", r, g, b)),
-        Print(SPACE),
+        Print(SEPARATOR),
         PrintBold("HSV: "),
         Print(format!(
             "{:>3.0}, {:>3.2}%, {:>3.2}%",
@@ -30,6 +49,27 @@ pub fn draw_value_display(pos: &Vec2, color: &Hsv, fade: bool) -> io::Result<()>
             color.saturation * 100.0,
             color.value * 100.0
         )),
+        Print(SEPARATOR),
+        PrintBold("CMYK: "),
+        Print(format!(
+            "{:>3.0}, {:>3.0}, {:>3.0}, {:>3.0}",
+            c * 100.0,
+            m * 100.0,
+            y * 100.0,
+            k * 100.0
+        )),
+        MoveTo(pos.x as u16, pos.y as u16 + 1),
+        Clear(ClearType::CurrentLine),
+        PrintBold(&format!("{:>8}", settings.white_point.name())),
+        Print(SEPARATOR),
+        PrintBold("LAB: "),
+        Print(format!("{:.2}, {:.2}, {:.2}", lab.0, lab.1, lab.2)),
+        Print(SEPARATOR),
+        PrintBold("LCH: "),
+        Print(format!("{:.2}, {:.2}, {:.2}", lch.0, lch.1, lch.2)),
+        Print(SEPARATOR),
+        PrintBold("XYZ: "),
+        Print(format!("{:.4}, {:.4}, {:.4}", xyz.0, xyz.1, xyz.2)),
     )?;
     Ok(())
 }
@@ -1,7 +1,10 @@
 use crate::constants::*;
 use crate::types::Vec2;
-use crossterm::event::MouseEvent;
+use crossterm::event::{Event, MouseEvent, MouseEventKind};
 use palette::{FromColor, Hsv, Srgb};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
 pub fn rgb_from_hsv(hsv: &Hsv) -> (u8, u8, u8) {
     Srgb::from_color(*hsv).into_format::<u8>().into_components()
@@ -11,6 +14,37 @@ pub fn hsv_from_rgb(r: u8, g: u8, b: u8) -> Hsv {
     Hsv::from_color(Srgb::new(r, g, b).into_format::<f32>())
 }
 
+/// Naive CMYK without a color profile, as fractions. Black has no cyan,
+/// magenta or yellow.
+pub fn cmyk_from_hsv(hsv: &Hsv) -> (f32, f32, f32, f32) {
+    let (r, g, b) = Srgb::from_color(*hsv).into_components();
+    let k = 1.0 - r.max(g).max(b);
+    if k >= 1.0 {
+        return (0.0, 0.0, 0.0, 1.0);
+    }
+    let ink = |channel: f32| ((1.0 - channel - k) / (1.0 - k)).clamp(0.0, 1.0);
+    (ink(r), ink(g), ink(b), k.clamp(0.0, 1.0))
+}
+
+pub fn hsv_from_cmyk(c: f32, m: f32, y: f32, k: f32) -> Hsv {
+    let channel = |ink: f32| (1.0 - ink) * (1.0 - k);
+    Hsv::from_color(Srgb::new(channel(c), channel(m), channel(y)))
+}
+
+/// At most `decimals` decimals, without trailing zeros
+pub fn format_decimal(value: f32, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value);
+    let text = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &text
+    };
+    match text {
+        "-0" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
+
 pub fn fade_color(mut color: Hsv) -> Hsv {
     color.value -= FADE_VALUE_FACTOR;
     color
@@ -28,6 +62,176 @@ pub fn normalize_pos(event: MouseEvent, pos: &Vec2) -> Option<Vec2> {
     })
 }
 
+/// Keeps only the last of consecutive drags of the same button, so that a
+/// burst of drag events read at once is drawn as one frame. Wheel events and
+/// everything else are kept, each scroll is a step.
+pub fn coalesce_drags(events: Vec<Event>) -> Vec<Event> {
+    let mut coalesced: Vec<Event> = Vec::with_capacity(events.len());
+    for event in events {
+        if let (Some(Event::Mouse(last)), Event::Mouse(mouse)) = (coalesced.last_mut(), &event)
+            && matches!(last.kind, MouseEventKind::Drag(_))
+            && last.kind == mouse.kind
+            && last.modifiers == mouse.modifiers
+        {
+            *last = *mouse;
+            continue;
+        }
+        coalesced.push(event);
+    }
+    coalesced
+}
+
 pub fn check_terminal_size(width: u16, height: u16) -> bool {
     width < (TOTAL_WIDTH + 2) as u16 || height < (TOTAL_HEIGHT + 2) as u16
 }
+
+/// Black or white, whichever is readable on top of the color
+pub fn contrast_text_color(color: &Hsv) -> (u8, u8, u8) {
+    let (r, g, b) = rgb_from_hsv(color);
+    let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    if luma > 140.0 {
+        (0, 0, 0)
+    } else {
+        (255, 255, 255)
+    }
+}
+
+/// Directory for files the app keeps between sessions, following the XDG
+/// base directory spec.
+pub fn app_state_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(base.join("tui-color-picker"))
+}
+
+/// `colors.json` written by pywal, and by wallust with its pywal template,
+/// in the XDG cache directory.
+pub fn wal_colors_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(base.join("wal/colors.json"))
+}
+
+/// Writes the file through a temporary file in the same directory and a
+/// rename, so readers never see a partially written file. An existing file
+/// keeps its permissions, and a symlink keeps pointing to it: the file it
+/// points to is the one replaced.
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let (path, permissions) = match fs::canonicalize(path) {
+        Ok(target) => {
+            let permissions = fs::metadata(&target)?.permissions();
+            (target, Some(permissions))
+        }
+        Err(_) => (path.to_path_buf(), None),
+    };
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+    fs::write(&tmp_path, content)?;
+    let result = match permissions {
+        Some(permissions) => fs::set_permissions(&tmp_path, permissions),
+        None => Ok(()),
+    };
+    result
+        .and_then(|()| fs::rename(&tmp_path, &path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp_path);
+        })
+}
+
+#[test]
+fn test_cmyk_round_trip() {
+    let primaries = [
+        ((255, 0, 0), (0.0, 1.0, 1.0, 0.0)),
+        ((0, 255, 0), (1.0, 0.0, 1.0, 0.0)),
+        ((0, 0, 255), (1.0, 1.0, 0.0, 0.0)),
+        ((0, 255, 255), (1.0, 0.0, 0.0, 0.0)),
+        ((255, 255, 255), (0.0, 0.0, 0.0, 0.0)),
+        ((0, 0, 0), (0.0, 0.0, 0.0, 1.0)),
+    ];
+    for ((r, g, b), cmyk) in primaries {
+        assert_eq!(cmyk_from_hsv(&hsv_from_rgb(r, g, b)), cmyk);
+    }
+    // Grays only use black, every RGB gray survives the round trip
+    for level in 0..=255 {
+        let (c, m, y, k) = cmyk_from_hsv(&hsv_from_rgb(level, level, level));
+        assert_eq!((c, m, y), (0.0, 0.0, 0.0));
+        assert_eq!(
+            rgb_from_hsv(&hsv_from_cmyk(c, m, y, k)),
+            (level, level, level)
+        );
+    }
+    for (r, g, b) in [(255, 87, 51), (29, 32, 33), (128, 0, 255)] {
+        let (c, m, y, k) = cmyk_from_hsv(&hsv_from_rgb(r, g, b));
+        assert_eq!(rgb_from_hsv(&hsv_from_cmyk(c, m, y, k)), (r, g, b));
+    }
+}
+
+#[test]
+fn test_coalesce_drags() {
+    use crossterm::event::{KeyModifiers, MouseButton};
+
+    let mouse = |kind, column| {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row: 5,
+            modifiers: KeyModifiers::NONE,
+        })
+    };
+    let drag = MouseEventKind::Drag(MouseButton::Left);
+    let scroll = MouseEventKind::ScrollUp;
+    let events = vec![
+        mouse(MouseEventKind::Down(MouseButton::Left), 1),
+        mouse(drag, 2),
+        mouse(drag, 3),
+        mouse(drag, 4),
+        mouse(scroll, 4),
+        mouse(scroll, 4),
+        mouse(drag, 5),
+        mouse(MouseEventKind::Up(MouseButton::Left), 5),
+    ];
+    assert_eq!(
+        coalesce_drags(events),
+        vec![
+            mouse(MouseEventKind::Down(MouseButton::Left), 1),
+            mouse(drag, 4),
+            mouse(scroll, 4),
+            mouse(scroll, 4),
+            mouse(drag, 5),
+            mouse(MouseEventKind::Up(MouseButton::Left), 5),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_atomic_keeps_link_and_mode() {
+    use std::os::unix::fs::{PermissionsExt, symlink};
+
+    let dir = std::env::temp_dir().join(format!("tui-color-picker-atomic-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (file, link) = (dir.join("colors.conf"), dir.join("link.conf"));
+    fs::write(&file, "old").unwrap();
+    fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+    symlink(&file, &link).unwrap();
+
+    write_atomic(&link, b"new").unwrap();
+    assert!(
+        fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+    let mode = fs::metadata(&file).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    write_atomic(&dir.join("new.conf"), b"created").unwrap();
+    assert_eq!(fs::read_to_string(dir.join("new.conf")).unwrap(), "created");
+    fs::remove_dir_all(&dir).unwrap();
+}
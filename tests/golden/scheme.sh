@@ -0,0 +1,21 @@
+#!/bin/sh
+# Golden "test", generated by tui-color-picker
+printf '\033]4;0;rgb:00/00/00\033\\'
+printf '\033]4;1;rgb:cc/24/1d\033\\'
+printf '\033]4;2;rgb:98/97/1a\033\\'
+printf '\033]4;3;rgb:cd/cd/00\033\\'
+printf '\033]4;4;rgb:00/00/ee\033\\'
+printf '\033]4;5;rgb:cd/00/cd\033\\'
+printf '\033]4;6;rgb:00/cd/cd\033\\'
+printf '\033]4;7;rgb:e5/e5/e5\033\\'
+printf '\033]4;8;rgb:7f/7f/7f\033\\'
+printf '\033]4;9;rgb:ff/00/00\033\\'
+printf '\033]4;10;rgb:00/ff/00\033\\'
+printf '\033]4;11;rgb:ff/ff/00\033\\'
+printf '\033]4;12;rgb:83/a5/98\033\\'
+printf '\033]4;13;rgb:ff/00/ff\033\\'
+printf '\033]4;14;rgb:00/ff/ff\033\\'
+printf '\033]4;15;rgb:ff/ff/ff\033\\'
+printf '\033]10;rgb:eb/db/b2\033\\'
+printf '\033]11;rgb:28/28/28\033\\'
+printf '\033]12;rgb:eb/db/b2\033\\'